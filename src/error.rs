@@ -12,32 +12,78 @@ pub enum Error {
     LobbyClosed,
     /// The lobby is full (from maxplayers), joining is not possible
     LobbyFull,
-    /// The lobby cannot be started, this happens because certain criteria are hit which will break the game
-    LobbyStart,
+    /// The lobby cannot be started, the requirement tells which criteria would break the game
+    LobbyStart(StartRequirement),
     /// The lobby is full (from maxplayers), joining is not possible
     LobbyNotFound,
     /// Card couldn't be submitted due to Game Phase missmatch
     CardSubmission,
     /// Czar couldn't choose due to Game Phase missmatch
     CzarChoice,
+    /// Vote couldn't be submitted due to Game Phase missmatch or voting for the own cards
+    Vote,
+    /// The hand couldn't be traded in, because it's disabled, in the wrong phase, too expensive or used up this round
+    Mulligan,
+    /// The game couldn't be paused or resumed, because it isn't running or already in that state
+    Pause,
+    /// The round couldn't be skipped, because none is being played or it's already skipped
+    SkipRound,
+    /// The settings are out of range, e.g. a minimum player count above the maximum
+    Settings(String),
+    /// The team doesn't exist, the lobby isn't open or has less than two teams configured
+    Team,
+    /// The chat message is empty or too long, was sent too fast or the sender is muted
+    Chat,
+    /// Czar couldn't redraw the black card due to Game Phase missmatch or no redraws left
+    BlackCardRedraw,
     /// Event send from player or source which is not authorized to do that action
     Unauthorized,
+    /// Nsfw decks are forbidden in this lobby or on this server
+    Nsfw,
     /// Deck related errors
     Deck(String),
     /// Reqwest related Errors
     Reqwest(String),
     /// File System Error
     FileSystem(String),
+    /// Zip Archive Error
+    Archive(String),
     /// Json Serialzing/Desializing Error
     Json(String),
 }
 
+/// The requirement which wasn't met when starting a game
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", content = "value")]
+pub enum StartRequirement {
+    /// The lobby isn't open or a game is still running
+    LobbyOpen,
+    /// Less players than the `min_players` setting
+    Players { required: u32, current: u32 },
+    /// Every team mode game needs at least two teams with players
+    Teams,
+    /// No deck is enabled or none of them could be loaded
+    Decks,
+    /// The enabled decks have no black cards
+    BlackCards,
+    /// The enabled decks have less white cards than a single hand holds
+    WhiteCards { required: usize, available: usize },
+    /// A black card needs more fields than a hand holds
+    HandSize { required: usize },
+}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Error::FileSystem(err.to_string())
     }
 }
 
+impl From<zip::result::ZipError> for Error {
+    fn from(err: zip::result::ZipError) -> Self {
+        Error::Archive(err.to_string())
+    }
+}
+
 impl From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
         Error::Reqwest(err.to_string())
@@ -57,12 +103,22 @@ impl IntoResponse for Error {
             | Error::LobbyLogin
             | Error::LobbyFull
             | Error::Json(_)
+            | Error::Archive(_)
+            | Error::Nsfw
+            | Error::Team
+            | Error::Settings(_)
+            | Error::Chat
             | Error::Deck(_) => StatusCode::BAD_REQUEST,
             Error::LobbyNotFound => StatusCode::NOT_FOUND,
             Error::Unauthorized => StatusCode::UNAUTHORIZED,
             Error::CardSubmission
-            | Error::LobbyStart
+            | Error::LobbyStart(_)
             | Error::CzarChoice
+            | Error::Vote
+            | Error::Pause
+            | Error::SkipRound
+            | Error::Mulligan
+            | Error::BlackCardRedraw
             | Error::FileSystem(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Error::Reqwest(_) => StatusCode::SERVICE_UNAVAILABLE,
         };
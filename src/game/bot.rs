@@ -0,0 +1,191 @@
+use std::{
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use rand::{seq::IndexedRandom, RngExt};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::broadcast::error::RecvError, task::JoinHandle, time::sleep};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, Result},
+    game::{
+        deck::{BlackCard, WhiteCard},
+        lobby::{GamePhase, Lobby},
+        GameMode, ServerEvent,
+    },
+};
+
+/// A server side player, deciding which cards to play and which submission wins
+pub trait Bot: Send + Sync {
+    /// Pick `black_card.fields` unique indexes into the hand
+    fn choose_cards(&self, black_card: &BlackCard, hand: &[WhiteCard]) -> Vec<usize>;
+    /// Pick the index of the winning submission as czar
+    fn choose_winner(&self, black_card: &BlackCard, submissions: &[Vec<WhiteCard>]) -> usize;
+}
+
+/// The strategies a host can choose from when adding a bot
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BotStrategy {
+    /// Plays and judges completely at random
+    Random,
+    /// Plays its longest cards and picks the longest submission
+    LongestText,
+}
+
+impl BotStrategy {
+    pub fn create(self) -> Box<dyn Bot> {
+        match self {
+            BotStrategy::Random => Box::new(RandomBot),
+            BotStrategy::LongestText => Box::new(LongestTextBot),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BotStrategy::Random => "Random Bot",
+            BotStrategy::LongestText => "Wordy Bot",
+        }
+    }
+}
+
+pub struct RandomBot;
+
+impl Bot for RandomBot {
+    fn choose_cards(&self, black_card: &BlackCard, hand: &[WhiteCard]) -> Vec<usize> {
+        let mut rng = rand::rng();
+        rand::seq::index::sample(&mut rng, hand.len(), black_card.fields.min(hand.len())).into_vec()
+    }
+
+    fn choose_winner(&self, _black_card: &BlackCard, submissions: &[Vec<WhiteCard>]) -> usize {
+        let indexes: Vec<usize> = (0..submissions.len()).collect();
+        indexes
+            .choose(&mut rand::rng())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+pub struct LongestTextBot;
+
+impl Bot for LongestTextBot {
+    fn choose_cards(&self, black_card: &BlackCard, hand: &[WhiteCard]) -> Vec<usize> {
+        let mut indexes: Vec<usize> = (0..hand.len()).collect();
+        indexes.sort_by_key(|&i| std::cmp::Reverse(hand[i].text.len()));
+        indexes.truncate(black_card.fields);
+        indexes
+    }
+
+    fn choose_winner(&self, _black_card: &BlackCard, submissions: &[Vec<WhiteCard>]) -> usize {
+        submissions
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, cards)| cards.iter().map(|c| c.text.len()).sum::<usize>())
+            .map(|(i, _)| i)
+            .unwrap_or_default()
+    }
+}
+
+/// Let the bot play in the lobby by reacting to the global events.
+/// Only holds a weak reference, so the task ends together with the lobby.
+pub fn spawn(lobby: &Arc<Lobby>, bot_id: Uuid, bot: Box<dyn Bot>) -> JoinHandle<()> {
+    let mut events = lobby.subscribe_global();
+    let lobby: Weak<Lobby> = Arc::downgrade(lobby);
+
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+            let Some(lobby) = lobby.upgrade() else {
+                return;
+            };
+            if let Err(e) = handle_event(&lobby, bot_id, bot.as_ref(), event).await {
+                info!("Bot {} couldn't act: {:?}", bot_id, e);
+            }
+        }
+    })
+}
+
+async fn handle_event(
+    lobby: &Lobby,
+    bot_id: Uuid,
+    bot: &dyn Bot,
+    event: ServerEvent,
+) -> Result<()> {
+    match event {
+        ServerEvent::StartRound { czar_id, .. } if czar_id != Some(bot_id) => {
+            think().await;
+            let (black_card, hand) = {
+                let guard = lobby.state.read().await;
+                if guard.phase != GamePhase::Submitting {
+                    return Ok(());
+                }
+                let hand = guard
+                    .players
+                    .get(&bot_id)
+                    .map(|p| p.cards.clone())
+                    .ok_or(Error::CardSubmission)?;
+                (guard.black_card.clone().unwrap_or_default(), hand)
+            };
+            let indexes = bot.choose_cards(&black_card, &hand);
+            lobby.submit_cards(&bot_id, indexes).await
+        }
+        ServerEvent::RevealCards { selected_cards } => {
+            let (black_card, game_mode) = {
+                let guard = lobby.state.read().await;
+                (
+                    guard.black_card.clone().unwrap_or_default(),
+                    guard.settings.game_mode,
+                )
+            };
+
+            if game_mode == GameMode::Voting {
+                vote(lobby, bot_id, bot).await
+            } else if lobby.is_czar(&bot_id).await {
+                think().await;
+                let index = bot.choose_winner(&black_card, &selected_cards);
+                lobby.submit_czar_choice(&bot_id, index).await
+            } else {
+                Ok(())
+            }
+        }
+        ServerEvent::CzarVote => vote(lobby, bot_id, bot).await,
+        _ => Ok(()),
+    }
+}
+
+/// Votes for the best submission other than the own one
+async fn vote(lobby: &Lobby, bot_id: Uuid, bot: &dyn Bot) -> Result<()> {
+    let (black_card, reveal, by_index) = {
+        let guard = lobby.state.read().await;
+        (
+            guard.black_card.clone().unwrap_or_default(),
+            guard.submissions.reveal.clone(),
+            guard.submissions.by_index.clone(),
+        )
+    };
+
+    // Only other submissions can be voted for
+    let others: Vec<usize> = (0..reveal.len())
+        .filter(|&i| by_index.get(i) != Some(&bot_id))
+        .collect();
+    if others.is_empty() {
+        return Ok(());
+    }
+    let candidates: Vec<Vec<WhiteCard>> = others.iter().map(|&i| reveal[i].clone()).collect();
+
+    think().await;
+    let index = others[bot.choose_winner(&black_card, &candidates)];
+    lobby.submit_vote(&bot_id, index).await
+}
+
+/// Bots take a moment, so they don't feel instant
+async fn think() {
+    let secs = rand::rng().random_range(1..=4);
+    sleep(Duration::from_secs(secs)).await;
+}
@@ -1,4 +1,7 @@
 use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::Read,
     path::{Path, PathBuf},
     time::{SystemTime, UNIX_EPOCH},
 };
@@ -10,10 +13,13 @@ use crate::{
 use rand::{rng, seq::IteratorRandom};
 use reqwest::Client;
 use serde::{Deserialize, Deserializer, Serialize};
-use serde_json;
+use serde_json::{self, Value};
 use tokio::{fs, io::AsyncWriteExt};
+use zip::ZipArchive;
 
 const API_BASE: &str = "https://api.crcast.cc/v1";
+/// Upper bound for a single deck file inside an archive (16 MiB)
+const MAX_IMPORT_FILE_SIZE: u64 = 16 * 1024 * 1024;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DeckInfo {
@@ -28,6 +34,28 @@ pub struct Deck {
     pub whites: Vec<WhiteCard>,
 }
 
+/// Per-file result of an archive import
+#[derive(Serialize, Debug, Clone)]
+pub struct ImportEntry {
+    pub file: String,
+    pub outcome: ImportOutcome,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "kind", content = "value")]
+pub enum ImportOutcome {
+    /// The deck was validated and saved into the cache, `replaced` an already cached deck
+    Imported {
+        deckcode: String,
+        name: String,
+        replaced: bool,
+    },
+    /// An earlier file of the archive has the same deckcode, this one was skipped
+    Duplicate { deckcode: String, file: String },
+    /// The file couldn't be read, parsed or validated
+    Failed(Error),
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DeckMeta {
     pub name: String,
@@ -38,6 +66,16 @@ pub struct DeckMeta {
     pub whites_count: usize,
     #[serde(default = "empty_timestamp")]
     pub fetched_at: u64,
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    #[serde(default)]
+    pub updated_at: Option<u64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 fn now() -> u64 {
@@ -94,6 +132,93 @@ impl Deck {
         Ok(deck)
     }
 
+    /// Parse a deck from any supported format: our own cache format or a crcast response
+    pub fn parse(data: &str) -> Result<Deck> {
+        let mut deck = if let Ok(deck) = serde_json::from_str::<Deck>(data) {
+            deck
+        } else if let Ok(api) = serde_json::from_str::<CrCastApiResponse>(data) {
+            api.into()
+        } else {
+            let deck = serde_json::from_str::<CrCastResponse>(data)?;
+            CrCastApiResponse { deck }.into()
+        };
+        deck.meta.fetched_at = now();
+        deck.validate()?;
+        Ok(deck)
+    }
+
+    /// Makes sure the deck is playable and can be stored safely
+    pub fn validate(&self) -> Result<()> {
+        let code = &self.meta.deckcode;
+        if code.is_empty()
+            || !code
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::Deck(format!("Invalid deckcode {code:?}")));
+        }
+        if self.meta.name.trim().is_empty() {
+            return Err(Error::Deck(String::from("Deck has no name")));
+        }
+        if self.blacks.is_empty() && self.whites.is_empty() {
+            return Err(Error::Deck(String::from("Deck has no cards")));
+        }
+        if self.blacks.iter().any(|b| b.fields == 0) {
+            return Err(Error::Deck(String::from("Black card without a field")));
+        }
+        Ok(())
+    }
+
+    /// Import every deck inside of a zip archive into the cache.
+    /// A broken deck doesn't abort the import, it's only reported as failed.
+    pub async fn import_archive(cache: &Path, archive: &Path) -> Result<Vec<ImportEntry>> {
+        let mut zip = ZipArchive::new(File::open(archive)?)?;
+
+        let mut report = Vec::new();
+        // Deckcode to the file it was imported from
+        let mut imported: HashMap<String, String> = HashMap::new();
+        for i in 0..zip.len() {
+            let (file, content) = {
+                let mut entry = zip.by_index(i)?;
+                let file = entry.name().to_owned();
+                // Skip folders and the metadata macOS adds to archives
+                if entry.is_dir() || file.starts_with("__MACOSX/") {
+                    continue;
+                }
+                (file, read_entry(&mut entry))
+            };
+
+            let outcome = match content.and_then(|data| Deck::parse(&data)) {
+                Ok(deck) => {
+                    if let Some(first) = imported.get(&deck.meta.deckcode) {
+                        ImportOutcome::Duplicate {
+                            deckcode: deck.meta.deckcode,
+                            file: first.clone(),
+                        }
+                    } else {
+                        let path = Self::cache_file_path(cache, &deck.meta.deckcode);
+                        let replaced = fs::try_exists(&path).await.unwrap_or(false);
+                        match deck.save(cache).await {
+                            Ok(()) => {
+                                imported.insert(deck.meta.deckcode.clone(), file.clone());
+                                ImportOutcome::Imported {
+                                    deckcode: deck.meta.deckcode,
+                                    name: deck.meta.name,
+                                    replaced,
+                                }
+                            }
+                            Err(e) => ImportOutcome::Failed(e),
+                        }
+                    }
+                }
+                Err(e) => ImportOutcome::Failed(e),
+            };
+            report.push(ImportEntry { file, outcome });
+        }
+
+        Ok(report)
+    }
+
     /// To format decks into the DeckInfo used in Settings
     fn into_infos(decks: Vec<Deck>, last_info: Option<Vec<DeckInfo>>) -> Vec<DeckInfo> {
         let mut infos: Vec<DeckInfo> = decks
@@ -106,20 +231,24 @@ impl Deck {
 
         // Apply enabled from last_info
         if let Some(last_info) = last_info {
-            for before in last_info {
-                if let Some((i, _)) = infos
-                    .iter()
-                    .enumerate()
-                    .find(|(_i, d)| d.meta.deckcode == before.meta.deckcode)
-                {
-                    infos[i].enabled = before.enabled;
-                }
-            }
+            Self::apply_enabled(&mut infos, &last_info);
         }
 
         infos
     }
 
+    /// Copies the enabled state from `last_info` onto `infos`, matched by deckcode
+    pub fn apply_enabled(infos: &mut [DeckInfo], last_info: &[DeckInfo]) {
+        for before in last_info {
+            if let Some(info) = infos
+                .iter_mut()
+                .find(|d| d.meta.deckcode == before.meta.deckcode)
+            {
+                info.enabled = before.enabled;
+            }
+        }
+    }
+
     /// Lists all downloaded deck infos (simply loading from disk)
     pub async fn get_all_cached_info(
         cache: &PathBuf,
@@ -199,29 +328,54 @@ impl Deck {
     }
 }
 
+/// Read a single archive entry as text, rejecting non json and oversized files
+fn read_entry(entry: &mut zip::read::ZipFile<'_>) -> Result<String> {
+    if !entry.name().to_lowercase().ends_with(".json") {
+        return Err(Error::Deck(String::from("Unsupported file type")));
+    }
+    if entry.size() > MAX_IMPORT_FILE_SIZE {
+        return Err(Error::Deck(String::from("File is too large")));
+    }
+
+    let mut data = String::new();
+    entry.take(MAX_IMPORT_FILE_SIZE).read_to_string(&mut data)?;
+    Ok(data)
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct WhiteCard {
     pub text: String,
 }
 
 impl WhiteCard {
-    /// Pick multiple random white cards (up to `count`) from all cached decks
+    /// Pick multiple random white cards (up to `count`) from all cached decks.
+    /// Discarded cards are only dealt again when there aren't enough others left.
     pub async fn choose_random(
         cache: &Path,
         count: usize,
         settings: &Settings,
+        discard: &[WhiteCard],
     ) -> Result<Vec<WhiteCard>> {
         let decks = Deck::get_enabled(cache, settings).await?;
 
         if !decks.is_empty() {
             let mut rng = rng();
-            let whites: Vec<WhiteCard> = decks
-                .iter()
-                .flat_map(|d| d.whites.iter())
-                .sample(&mut rng, count)
-                .into_iter()
-                .cloned()
-                .collect();
+            let discarded: HashSet<&str> = discard.iter().map(|c| c.text.as_str()).collect();
+            let all = decks.iter().flat_map(|d| d.whites.iter());
+            let fresh = all
+                .clone()
+                .filter(|c| !discarded.contains(c.text.as_str()))
+                .count();
+
+            let whites: Vec<WhiteCard> = if fresh >= count {
+                all.filter(|c| !discarded.contains(c.text.as_str()))
+                    .sample(&mut rng, count)
+            } else {
+                all.sample(&mut rng, count)
+            }
+            .into_iter()
+            .cloned()
+            .collect();
 
             if whites.is_empty() {
                 Err(Error::Deck(String::from("No white cards available")))
@@ -282,6 +436,16 @@ struct CrCastResponse {
     raw_blacks_count: usize,
     #[serde(rename = "whiteCount")]
     raw_whites_count: usize,
+    #[serde(default)]
+    author: Option<Value>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default, rename = "createdAt")]
+    created_at: Option<Value>,
+    #[serde(default, rename = "updatedAt")]
+    updated_at: Option<Value>,
+    #[serde(default)]
+    tags: Option<Value>,
 }
 
 fn bool_from_int<'de, D>(deserializer: D) -> std::result::Result<bool, D::Error>
@@ -292,6 +456,55 @@ where
     Ok(v == 1)
 }
 
+/// The author is either sent as a plain name or as an user object
+fn author_from_value(value: Value) -> Option<String> {
+    match value {
+        Value::String(name) => Some(name),
+        Value::Object(user) => ["name", "username", "displayName"]
+            .iter()
+            .find_map(|key| user.get(*key).and_then(Value::as_str))
+            .map(str::to_owned),
+        _ => None,
+    }
+    .filter(|name| !name.trim().is_empty())
+}
+
+/// Timestamps are either unix seconds, unix milliseconds or numeric strings
+fn timestamp_from_value(value: Value) -> Option<u64> {
+    let raw = match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }?;
+    // Everything this large can only be in milliseconds
+    if raw > 100_000_000_000 {
+        Some(raw / 1000)
+    } else {
+        Some(raw)
+    }
+}
+
+/// Tags are either a list of strings or a single comma separated string
+fn tags_from_value(value: Value) -> Vec<String> {
+    let tags: Vec<String> = match value {
+        Value::Array(values) => values
+            .into_iter()
+            .filter_map(|v| match v {
+                Value::String(s) => Some(s),
+                Value::Object(tag) => tag.get("name").and_then(Value::as_str).map(str::to_owned),
+                _ => None,
+            })
+            .collect(),
+        Value::String(s) => s.split(',').map(str::to_owned).collect(),
+        _ => Vec::new(),
+    };
+
+    tags.into_iter()
+        .map(|t| t.trim().to_owned())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
 #[derive(Deserialize, Debug)]
 struct RawCard {
     pub text: String,
@@ -334,6 +547,11 @@ impl From<CrCastApiResponse> for Deck {
                 blacks_count: deck.raw_blacks_count,
                 whites_count: deck.raw_whites_count,
                 fetched_at: empty_timestamp(),
+                author: deck.author.and_then(author_from_value),
+                description: deck.description.filter(|d| !d.trim().is_empty()),
+                created_at: deck.created_at.and_then(timestamp_from_value),
+                updated_at: deck.updated_at.and_then(timestamp_from_value),
+                tags: deck.tags.map(tags_from_value).unwrap_or_default(),
             },
             blacks,
             whites,
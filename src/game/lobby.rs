@@ -1,5 +1,9 @@
 use dashmap::DashMap;
-use rand::seq::SliceRandom;
+use rand::{
+    rngs::StdRng,
+    seq::{IndexedRandom, SliceRandom},
+    Rng, RngExt, SeedableRng,
+};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet, VecDeque},
@@ -8,6 +12,7 @@ use std::{
     sync::Arc,
     time::Duration,
 };
+use tokio::task::JoinHandle;
 use tokio::{
     sync::{
         broadcast::{Receiver, Sender},
@@ -16,20 +21,38 @@ use tokio::{
     },
     time::Instant,
 };
-use tokio::{task::JoinHandle, time::sleep};
 use tracing::{error, info};
 use uuid::Uuid;
 
 use crate::{
-    error::{Error, Result},
+    error::{Error, Result, StartRequirement},
     game::{
+        bot::{self, BotStrategy},
         deck::{BlackCard, Deck, DeckInfo, WhiteCard},
-        ClientLobby, Credentials, Player, PlayerInfo, PrivateServerEvent, ServerEvent, Settings,
+        ChatMessage, ClientLobby, Credentials, CzarRotation, CzarTimeout, GameMode, GameOverReason,
+        Mulligan, Placement, Player, PlayerInfo, PrivateServerEvent, ServerEvent, Settings,
+        Spectator, Standing, TeamStanding, TieBreak, VoteTie, RANDO_ID,
     },
-    utils::all_unique,
+    utils::{all_unique, unix_millis},
     GRACE_PERIOD,
 };
 
+/// Fewer players can't play a game
+pub const MIN_PLAYERS: usize = 2;
+/// More cards don't fit on any screen
+pub const MAX_HAND_SIZE: usize = 20;
+/// Players vote this long when the czar didn't pick in time
+pub const FALLBACK_VOTE_SECS: u64 = 15;
+/// Players still tied after these extra rounds share the win
+pub const MAX_SUDDEN_DEATH_ROUNDS: u32 = 3;
+/// Longer chat messages are rejected
+pub const MAX_CHAT_LENGTH: usize = 300;
+/// How many chat messages are kept for (re)joining clients
+pub const CHAT_HISTORY: usize = 50;
+/// Each sender may write this many messages per `CHAT_RATE_WINDOW`
+pub const CHAT_RATE_LIMIT: usize = 5;
+pub const CHAT_RATE_WINDOW: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Default, Clone)]
 pub struct Submissions {
     /// Cards as revealed to clients (outer index picked by czar)
@@ -38,6 +61,8 @@ pub struct Submissions {
     pub by_index: Vec<Uuid>,
     /// Per-player submitted indexes (indexes into their current hand at submit-time)
     pub submitted_by_player: HashMap<Uuid, Vec<usize>>,
+    /// Players whose cards the server submitted after they missed the deadline
+    pub auto_submitted: HashSet<Uuid>,
 }
 
 impl Submissions {
@@ -45,6 +70,7 @@ impl Submissions {
         self.reveal.clear();
         self.by_index.clear();
         self.submitted_by_player.clear();
+        self.auto_submitted.clear();
     }
 
     pub fn len(&self) -> usize {
@@ -66,6 +92,15 @@ impl Submissions {
         self.apply_permutation(&order);
     }
 
+    /// Drops the submission of a player, returns the index it had in `reveal`
+    pub fn remove_player(&mut self, player_id: &Uuid) -> Option<usize> {
+        self.submitted_by_player.remove(player_id);
+        let index = self.by_index.iter().position(|id| id == player_id)?;
+        self.reveal.remove(index);
+        self.by_index.remove(index);
+        Some(index)
+    }
+
     fn apply_permutation(&mut self, order: &[usize]) {
         debug_assert_eq!(self.reveal.len(), self.by_index.len());
         let mut new_reveal = Vec::with_capacity(self.reveal.len());
@@ -79,20 +114,474 @@ impl Submissions {
     }
 }
 
+/// Deadline of the current phase, which can be frozen by the host
+#[derive(Debug, Default, Clone)]
+pub struct PhaseTimer {
+    /// Full length of the current phase, `None` waits without a timer
+    pub duration: Option<Duration>,
+    /// When the current phase ends, unset while paused
+    pub deadline: Option<Instant>,
+    /// Time left when the timer got paused
+    pub remaining: Option<Duration>,
+    /// Stays paused across phases until resumed
+    pub paused: bool,
+}
+
+impl PhaseTimer {
+    pub fn start(&mut self, duration: Option<Duration>) {
+        self.duration = duration;
+        if self.paused {
+            self.deadline = None;
+            self.remaining = duration;
+        } else {
+            self.deadline = duration.map(|d| Instant::now() + d);
+            self.remaining = None;
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.start(self.duration);
+    }
+
+    pub fn stop(&mut self) {
+        self.duration = None;
+        self.deadline = None;
+        self.remaining = None;
+    }
+
+    /// Unix timestamp in milliseconds when the current phase ends, `None` without a timer or while paused
+    pub fn deadline_ms(&self) -> Option<u64> {
+        self.deadline.map(|d| {
+            let remaining = d.saturating_duration_since(Instant::now());
+            unix_millis() + remaining.as_millis() as u64
+        })
+    }
+
+    /// Returns the time left in the current phase
+    pub fn pause(&mut self) -> Option<Duration> {
+        self.paused = true;
+        self.remaining = self
+            .deadline
+            .take()
+            .map(|d| d.saturating_duration_since(Instant::now()));
+        self.remaining
+    }
+
+    /// Returns the time left in the current phase
+    pub fn resume(&mut self) -> Option<Duration> {
+        self.paused = false;
+        let remaining = self.remaining.take();
+        self.deadline = remaining.map(|r| Instant::now() + r);
+        remaining
+    }
+}
+
+/// Sleeps until the deadline, forever without one
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
 /// Inner mutable state of a lobby, protected by RwLock.
 #[derive(Debug, Default)]
 pub struct LobbyData {
     pub settings: Settings,
     pub players: HashMap<Uuid, Player>,
+    pub pending: Vec<(Uuid, Player)>, // joined a running game, dealt in next round
+    pub spectators: HashMap<Uuid, Spectator>,
     pub czar_order: VecDeque<Uuid>,
     pub round: u32,
     pub black_card: Option<BlackCard>,
     pub submissions: Submissions,
     pub czar_pick: Option<usize>,
+    pub czar_ranking: Vec<usize>,
+    pub placements: Vec<Placement>,
+    pub votes: HashMap<Uuid, usize>,
+    pub black_card_redraws: u32,
+    pub round_skipped: bool,
+    pub team_points: Vec<u32>,
+    pub czar_team: Option<usize>,        // team of the last czar
+    pub czar_rounds: HashMap<Uuid, u32>, // last round each player was czar
+    pub forced_czar: Option<Uuid>,
+    pub last_winner: Option<Uuid>,
+    pub seed: u64,
+    pub discard: Vec<WhiteCard>,
+    pub mulligans: HashMap<Uuid, u32>, // trade-ins this round
+    pub fallback_vote: bool,           // players vote, because the czar didn't pick in time
+    pub czar_timed_out: bool,
+    pub redrawing: bool, // no submissions while the czar's new black card is drawn
+    pub missed: HashMap<Uuid, u32>, // rounds missed in a row
+    pub chat: VecDeque<ChatMessage>, // the last `CHAT_HISTORY` messages
+    pub chat_times: HashMap<Uuid, VecDeque<Instant>>, // recent messages per sender
+    pub muted: HashSet<Uuid>,
+    pub rounds_won: HashMap<Uuid, u32>,
+    pub sudden_death: Vec<Uuid>, // the tied players, empty outside of a sudden death
+    pub sudden_death_rounds: u32,
+    pub winners: Vec<Uuid>,
+    pub standings: Vec<Standing>,
+    pub team_standings: Vec<TeamStanding>,
+    pub game_over_reason: Option<GameOverReason>,
+    pub timer: PhaseTimer,
     pub phase: GamePhase,
 }
 
 impl LobbyData {
+    pub fn czar_submitted(&self) -> bool {
+        self.czar_pick.is_some()
+    }
+
+    /// AFK players aren't waited for, unless nobody else but Rando could submit and a timer runs.
+    /// Then the phase runs until everyone submitted or the timer ends, so they can come back.
+    /// Without a timer it would never end, so the round goes on without them.
+    pub fn all_player_submitted(&self) -> bool {
+        let eligible: Vec<&Uuid> = self
+            .players
+            .keys()
+            .filter(|id| !self.is_czar(id) && !self.sits_out(id))
+            .collect();
+        let active = eligible.iter().filter(|id| !self.is_afk(id)).count();
+        let only_afk = !eligible
+            .iter()
+            .any(|id| !self.is_afk(id) && **id != RANDO_ID);
+        let submitters = if only_afk && self.timer.duration.is_some() {
+            eligible.len()
+        } else {
+            active
+        };
+        self.submissions.len() >= submitters
+    }
+
+    pub fn is_czar(&self, player_id: &Uuid) -> bool {
+        self.players.get(player_id).is_some_and(|p| p.info.is_czar)
+    }
+
+    pub fn is_afk(&self, player_id: &Uuid) -> bool {
+        self.players.get(player_id).is_some_and(|p| p.info.is_afk)
+    }
+
+    /// Teammates of the czar don't submit in the team mode
+    /// Only the tied players submit during a sudden death
+    pub fn sits_out(&self, player_id: &Uuid) -> bool {
+        if !self.sudden_death.is_empty() && !self.sudden_death.contains(player_id) {
+            return true;
+        }
+        let Some(team) = self.players.get(player_id).and_then(|p| p.info.team) else {
+            return false;
+        };
+        self.players
+            .values()
+            .any(|p| p.info.is_czar && p.info.team == Some(team))
+    }
+
+    /// The players with the most points grouped by side,
+    /// which is a single player or all members of a team in the team mode
+    pub fn leaders(&self) -> Vec<Vec<Uuid>> {
+        if self.settings.team_count() > 0 {
+            let mut members: Vec<Vec<Uuid>> = vec![Vec::new(); self.team_points.len()];
+            for (&id, p) in &self.players {
+                if let Some(team) = p.info.team.and_then(|t| members.get_mut(t)) {
+                    team.push(id);
+                }
+            }
+            let best = members
+                .iter()
+                .zip(&self.team_points)
+                .filter(|(m, _)| !m.is_empty())
+                .map(|(_, &points)| points)
+                .max();
+            members
+                .into_iter()
+                .zip(&self.team_points)
+                .filter(|(m, &points)| !m.is_empty() && Some(points) == best)
+                .map(|(m, _)| m)
+                .collect()
+        } else {
+            let best = self.players.values().map(|p| p.info.points).max();
+            self.players
+                .iter()
+                .filter(|(_, p)| Some(p.info.points) == best)
+                .map(|(&id, _)| vec![id])
+                .collect()
+        }
+    }
+
+    /// The winners after applying the tie-break, several ones share the win
+    pub fn winners(&self) -> Vec<Uuid> {
+        let mut leaders = self.leaders();
+        if self.settings.tie_break == TieBreak::RoundsWon && leaders.len() > 1 {
+            let wins = |side: &Vec<Uuid>| -> u32 {
+                side.iter().filter_map(|id| self.rounds_won.get(id)).sum()
+            };
+            let most = leaders.iter().map(wins).max().unwrap_or_default();
+            leaders.retain(|side| wins(side) == most);
+        }
+        leaders.into_iter().flatten().collect()
+    }
+
+    /// Every player ordered by points, then by rounds won
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .players
+            .iter()
+            .map(|(&player_id, p)| Standing {
+                player_id,
+                points: p.info.points,
+                name: p.info.name.clone(),
+                rounds_won: self.rounds_won.get(&player_id).copied().unwrap_or_default(),
+            })
+            .collect();
+        standings.sort_by_key(|s| std::cmp::Reverse((s.points, s.rounds_won)));
+        standings
+    }
+
+    /// Every team ordered by points, then by rounds won, empty without the team mode
+    pub fn team_standings(&self) -> Vec<TeamStanding> {
+        let Some(names) = &self.settings.teams else {
+            return Vec::new();
+        };
+        let mut standings: Vec<TeamStanding> = names
+            .iter()
+            .enumerate()
+            .map(|(team, name)| {
+                let player_ids: Vec<Uuid> = self
+                    .players
+                    .iter()
+                    .filter(|(_, p)| p.info.team == Some(team))
+                    .map(|(&id, _)| id)
+                    .collect();
+                TeamStanding {
+                    team,
+                    name: name.clone(),
+                    points: self.team_points.get(team).copied().unwrap_or_default(),
+                    rounds_won: player_ids
+                        .iter()
+                        .filter_map(|id| self.rounds_won.get(id))
+                        .sum(),
+                    player_ids,
+                }
+            })
+            .collect();
+        standings.sort_by_key(|s| std::cmp::Reverse((s.points, s.rounds_won)));
+        standings
+    }
+
+    /// Puts every teamless player into the smallest team, returns the assignments
+    pub fn balance_teams(&mut self) -> Vec<(Uuid, usize)> {
+        let team_count = self.settings.team_count();
+        if team_count == 0 {
+            return Vec::new();
+        }
+
+        let mut sizes = vec![0usize; team_count];
+        for team in self.players.values().filter_map(|p| p.info.team) {
+            if let Some(size) = sizes.get_mut(team) {
+                *size += 1;
+            }
+        }
+
+        let mut assigned = Vec::new();
+        for (&id, player) in self.players.iter_mut() {
+            if player.info.is_rando || player.info.team.is_some() {
+                continue;
+            }
+            let Some((team, size)) = sizes.iter_mut().enumerate().min_by_key(|(_, size)| **size)
+            else {
+                break;
+            };
+            *size += 1;
+            player.info.team = Some(team);
+            assigned.push((id, team));
+        }
+        assigned
+    }
+
+    /// Count of teams with at least one player
+    pub fn filled_teams(&self) -> usize {
+        let teams: HashSet<usize> = self.players.values().filter_map(|p| p.info.team).collect();
+        teams.len()
+    }
+
+    /// Takes the next czar out of the queue according to the czar rotation.
+    /// In the team mode only players of the team after the last czar's are considered.
+    pub fn next_czar<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<Uuid> {
+        let forced = self
+            .forced_czar
+            .take()
+            .filter(|id| self.czar_order.contains(id));
+        let last_winner = self.last_winner.take();
+
+        let chosen = match forced {
+            Some(id) => id,
+            None => {
+                // The back of the queue is next in the sequential rotation
+                let mut candidates: Vec<Uuid> = self.czar_order.iter().rev().copied().collect();
+                // The tied players should rather submit than judge
+                if candidates.iter().any(|id| !self.sudden_death.contains(id)) {
+                    candidates.retain(|id| !self.sudden_death.contains(id));
+                }
+                // AFK players only judge if nobody else is left
+                if candidates.iter().any(|id| !self.is_afk(id)) {
+                    candidates.retain(|id| !self.is_afk(id));
+                }
+                let team_count = self.settings.team_count();
+                if team_count > 0 {
+                    let start = self.czar_team.map(|t| t + 1).unwrap_or_default();
+                    let team_of = |id: &Uuid| self.players.get(id).and_then(|p| p.info.team);
+                    let next_team = (start..start + team_count)
+                        .map(|t| t % team_count)
+                        .find(|&t| candidates.iter().any(|id| team_of(id) == Some(t)));
+                    if let Some(team) = next_team {
+                        candidates.retain(|id| team_of(id) == Some(team));
+                    }
+                }
+
+                match self.settings.czar_rotation {
+                    CzarRotation::Sequential => candidates.first().copied(),
+                    CzarRotation::Random => candidates.choose(rng).copied(),
+                    CzarRotation::Winner => last_winner
+                        .filter(|id| candidates.contains(id))
+                        .or_else(|| candidates.first().copied()),
+                    CzarRotation::LeastRecent => candidates
+                        .iter()
+                        .min_by_key(|id| self.czar_rounds.get(id))
+                        .copied(),
+                }?
+            }
+        };
+
+        self.czar_order.retain(|id| *id != chosen);
+        self.czar_rounds.insert(chosen, self.round);
+        if let Some(team) = self.players.get(&chosen).and_then(|p| p.info.team) {
+            self.czar_team = Some(team);
+        }
+        Some(chosen)
+    }
+
+    /// Everyone who is able to vote for another submission did so,
+    /// the timed out czar isn't waited for in a fallback vote
+    pub fn all_player_voted(&self) -> bool {
+        self.players
+            .iter()
+            .filter(|(_, p)| !p.info.is_rando && !p.info.is_afk)
+            .filter(|(_, p)| !(self.fallback_vote && p.info.is_czar))
+            .filter(|(id, _)| self.submissions.by_index.iter().any(|s| s != *id))
+            .all(|(id, _)| self.votes.contains_key(id))
+    }
+
+    /// Count of all players taking a seat, so everyone except Rando
+    pub fn player_count(&self) -> usize {
+        self.players.values().filter(|p| !p.info.is_rando).count()
+    }
+
+    /// Name of a player, pending player or spectator, everyone who can chat
+    pub fn chat_name(&self, id: &Uuid) -> Option<String> {
+        if let Some(player) = self.players.get(id) {
+            return Some(player.info.name.clone());
+        }
+        if let Some((_, player)) = self.pending.iter().find(|(pending_id, _)| pending_id == id) {
+            return Some(player.info.name.clone());
+        }
+        self.spectators.get(id).map(|s| s.name.clone())
+    }
+
+    /// The mulligan settings, if the player may trade in their hand right now
+    pub fn mulligan_allowed(&self, player_id: &Uuid) -> Option<Mulligan> {
+        let mulligan = self.settings.mulligan.clone()?;
+        let used = self.mulligans.get(player_id).copied().unwrap_or_default();
+        let player = self.players.get(player_id)?;
+        // Submitted cards are still referenced by their index into the hand
+        let allowed = mulligan.phases.contains(&self.phase)
+            && !matches!(self.phase, GamePhase::LobbyOpen | GamePhase::GameOver)
+            && !self.submissions.submitted_by_player.contains_key(player_id)
+            && used < mulligan.per_round
+            && !player.info.is_rando
+            && player.info.points >= mulligan.cost;
+        allowed.then_some(mulligan)
+    }
+
+    /// Moves the pending players into the game, they are the last ones to become czar
+    pub fn seat_pending(&mut self) -> Vec<(Uuid, PlayerInfo)> {
+        let scores: Vec<u32> = self
+            .players
+            .values()
+            .filter(|p| !p.info.is_rando)
+            .map(|p| p.info.points)
+            .collect();
+        let points = self.settings.catch_up.points(&scores);
+
+        let mut ids = Vec::with_capacity(self.pending.len());
+        for (id, mut player) in std::mem::take(&mut self.pending) {
+            player.info.points = points;
+            self.players.insert(id, player);
+            self.czar_order.push_front(id);
+            ids.push(id);
+        }
+        self.balance_teams();
+
+        ids.into_iter()
+            .filter_map(|id| self.players.get(&id).map(|p| (id, p.info.clone())))
+            .collect()
+    }
+
+    /// Takes a leaving player out of the current round, realigning all indexes into the submissions.
+    /// Returns the index of the dropped submission.
+    pub fn drop_from_round(&mut self, player_id: &Uuid) -> Option<usize> {
+        let was_czar = self
+            .players
+            .get(player_id)
+            .map(|p| p.info.is_czar)
+            .unwrap_or(false);
+        if was_czar && matches!(self.phase, GamePhase::Submitting | GamePhase::Judging) {
+            self.skip_round();
+        }
+
+        self.votes.remove(player_id);
+        let removed = self.submissions.remove_player(player_id)?;
+
+        // Indexes behind the removed one move one to the front
+        let realign = |index: usize| match index.cmp(&removed) {
+            std::cmp::Ordering::Less => Some(index),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(index - 1),
+        };
+        self.votes = std::mem::take(&mut self.votes)
+            .into_iter()
+            .filter_map(|(id, index)| realign(index).map(|index| (id, index)))
+            .collect();
+        self.czar_ranking = self
+            .czar_ranking
+            .iter()
+            .filter_map(|&i| realign(i))
+            .collect();
+        self.placements = std::mem::take(&mut self.placements)
+            .into_iter()
+            .filter_map(|p| realign(p.card_index).map(|card_index| Placement { card_index, ..p }))
+            .collect();
+        if self.phase == GamePhase::RoundFinished {
+            // The result is final, a leaving winner doesn't hand the win to the next place
+            self.czar_pick = self.czar_pick.and_then(realign);
+        } else {
+            self.czar_pick = self.czar_ranking.first().copied();
+        }
+
+        Some(removed)
+    }
+
+    /// Voids the current round, submitted cards stay in the hands
+    pub fn skip_round(&mut self) {
+        self.round_skipped = true;
+        self.submissions.submitted_by_player.clear();
+    }
+
+    /// Whether any player is connected as a client
+    pub fn has_humans(&self) -> bool {
+        self.players
+            .values()
+            .any(|p| !p.info.is_rando && !p.info.is_bot)
+    }
+
     pub fn snapshot_for(&self, player_id: &Uuid) -> ClientLobby {
         let players = self
             .players
@@ -100,6 +589,18 @@ impl LobbyData {
             .map(|(&id, p)| (id, p.info.clone()))
             .collect();
 
+        let pending = self
+            .pending
+            .iter()
+            .map(|(id, p)| (*id, p.info.clone()))
+            .collect();
+
+        let spectators = self
+            .spectators
+            .iter()
+            .map(|(&id, s)| (id, s.name.clone()))
+            .collect();
+
         let hand = if self.phase != GamePhase::LobbyOpen {
             self.players.get(player_id).map(|p| p.cards.clone())
         } else {
@@ -131,6 +632,12 @@ impl LobbyData {
             vec![]
         };
 
+        let voted_players = if self.phase == GamePhase::Judging {
+            self.votes.keys().copied().collect()
+        } else {
+            vec![]
+        };
+
         let winner = if let Some(index) = self.czar_pick {
             self.submissions.by_index.get(index).copied()
         } else {
@@ -145,6 +652,8 @@ impl LobbyData {
 
         ClientLobby {
             players,
+            pending,
+            spectators,
             settings: self.settings.clone(),
             phase: self.phase,
             round: self.round,
@@ -152,9 +661,21 @@ impl LobbyData {
             revealed_cards,
             submitted_players,
             selected_cards,
+            voted_players,
+            paused: self.timer.paused,
+            team_points: self.team_points.clone(),
+            deadline: self.timer.deadline_ms(),
+            sudden_death: self.sudden_death.clone(),
+            winners: self.winners.clone(),
+            standings: self.standings.clone(),
+            team_standings: self.team_standings.clone(),
+            game_over_reason: self.game_over_reason,
             czar_pick: self.czar_pick,
             winner,
+            placements: self.placements.clone(),
             black_card,
+            chat: self.chat.iter().cloned().collect(),
+            muted: self.muted.iter().copied().collect(),
         }
     }
 }
@@ -163,13 +684,16 @@ impl LobbyData {
 pub struct Lobby {
     pub game_task: RwLock<Option<JoinHandle<()>>>,
     pub disconnect_timers: DashMap<Uuid, JoinHandle<()>>,
+    pub bots: DashMap<Uuid, JoinHandle<()>>,
     pub global: Sender<ServerEvent>, // broadcast to all clients
     pub private: DashMap<Uuid, UnboundedSender<PrivateServerEvent>>,
     pub cache: PathBuf,
+    pub allow_nsfw: bool,         // server wide nsfw policy
     pub state: RwLock<LobbyData>, // game state
     pub last_activity: RwLock<Instant>,
     pub submission_notify: Notify,
-    pub czar_notify: Notify,
+    pub czar_notify: Notify, // also used for votes
+    pub timer_notify: Notify,
 }
 
 /// Discrete phases in a round
@@ -191,25 +715,32 @@ impl Drop for Lobby {
 
 impl Lobby {
     /// Create a new lobby with host as first player.
-    pub async fn new(cache: PathBuf, host: Credentials) -> Result<Arc<Self>> {
+    pub async fn new(cache: PathBuf, allow_nsfw: bool, host: Credentials) -> Result<Arc<Self>> {
         let lobby = Arc::new(Self {
             game_task: RwLock::new(None),
             disconnect_timers: DashMap::new(),
+            bots: DashMap::new(),
             global: Sender::new(100),
             private: DashMap::new(),
             state: RwLock::new(LobbyData::default()),
             submission_notify: Notify::new(),
             last_activity: RwLock::new(Instant::now()),
             czar_notify: Notify::new(),
+            timer_notify: Notify::new(),
             cache: cache.clone(),
+            allow_nsfw,
         });
 
         // Initialize host in state
         {
             let mut guard = lobby.state.write().await;
-            guard.settings = Settings::default();
+            guard.settings = Settings {
+                allow_nsfw,
+                ..Default::default()
+            };
             let all_decks = Deck::get_all_cached_info(&cache, None).await?;
             guard.settings.decks = all_decks;
+            lobby.arrange_decks(&mut guard.settings);
             guard.round = 0;
             guard.phase = GamePhase::LobbyOpen;
             let host_player = Player {
@@ -219,6 +750,10 @@ impl Lobby {
                     is_host: true,
                     is_czar: false,
                     points: 0,
+                    is_rando: false,
+                    is_bot: false,
+                    team: None,
+                    is_afk: false,
                 },
                 cards: Vec::new(),
             };
@@ -282,6 +817,11 @@ impl Lobby {
 
     /// Player joins the lobby
     pub async fn join(&self, credentials: &Credentials) -> Result<()> {
+        // Nobody can pose as Rando
+        if credentials.id == RANDO_ID {
+            return Err(Error::LobbyLogin);
+        }
+
         let mut guard = self.state.write().await;
         let re_joining = guard.players.get(&credentials.id);
         let has_host = guard.players.values().any(|p| p.info.is_host);
@@ -304,37 +844,109 @@ impl Lobby {
             return Ok(());
         }
 
-        if guard.phase != GamePhase::LobbyOpen {
-            return Err(Error::LobbyClosed);
+        if let Some((_, player)) = guard.pending.iter().find(|(id, _)| *id == credentials.id) {
+            if player.info.name != credentials.name || player.secret != credentials.secret {
+                return Err(Error::LobbyLogin);
+            }
+
+            if let Some((_, handle)) = self.disconnect_timers.remove(&credentials.id) {
+                handle.abort();
+            }
+
+            return Ok(());
         }
 
+        // Spectators have to be promoted by the host
+        if guard.spectators.contains_key(&credentials.id) {
+            return Err(Error::LobbyLogin);
+        }
+
+        let late = match guard.phase {
+            GamePhase::LobbyOpen => false,
+            GamePhase::Submitting | GamePhase::Judging | GamePhase::RoundFinished
+                if guard.settings.join_in_progress =>
+            {
+                true
+            }
+            _ => return Err(Error::LobbyClosed),
+        };
+
         // Not rejoining, new player join
-        if guard.players.len() >= guard.settings.max_players as usize {
+        if guard.player_count() + guard.pending.len() >= guard.settings.max_players as usize {
             return Err(Error::LobbyFull);
         }
 
-        let is_first_player = guard.players.is_empty();
-
         let player_info = PlayerInfo {
             name: credentials.name.to_owned(),
-            is_host: is_first_player,
+            is_host: !has_host,
             is_czar: false,
             points: 0,
+            is_rando: false,
+            is_bot: false,
+            team: None,
+            is_afk: false,
         };
 
-        guard.players.insert(
+        let player = Player {
+            secret: credentials.secret,
+            info: player_info.clone(),
+            cards: Vec::new(),
+        };
+
+        if late {
+            // Wait for the next round to be dealt in
+            guard.pending.push((credentials.id, player));
+            self.emit_global(ServerEvent::PlayerPending {
+                player_id: credentials.id,
+                player_info,
+            });
+        } else {
+            guard.players.insert(credentials.id, player);
+            guard.czar_order.push_front(credentials.id);
+            self.emit_global(ServerEvent::PlayerJoin {
+                player_id: credentials.id,
+                player_info,
+            });
+        }
+
+        self.touch().await;
+
+        Ok(())
+    }
+
+    /// Spectator starts watching the lobby, possible in every phase
+    pub async fn spectate(&self, credentials: &Credentials) -> Result<()> {
+        if credentials.id == RANDO_ID {
+            return Err(Error::LobbyLogin);
+        }
+
+        let mut guard = self.state.write().await;
+
+        if let Some(spectator) = guard.spectators.get(&credentials.id) {
+            if spectator.name != credentials.name || spectator.secret != credentials.secret {
+                return Err(Error::LobbyLogin);
+            }
+            return Ok(());
+        }
+
+        // Players (also the pending ones) rejoin as players
+        if guard.players.contains_key(&credentials.id)
+            || guard.pending.iter().any(|(id, _)| *id == credentials.id)
+        {
+            return Err(Error::LobbyLogin);
+        }
+
+        guard.spectators.insert(
             credentials.id,
-            Player {
+            Spectator {
                 secret: credentials.secret,
-                info: player_info.clone(),
-                cards: Vec::new(),
+                name: credentials.name.to_owned(),
             },
         );
-        guard.czar_order.push_front(credentials.id);
 
-        self.emit_global(ServerEvent::PlayerJoin {
-            player_id: credentials.id,
-            player_info,
+        self.emit_global(ServerEvent::SpectatorJoin {
+            spectator_id: credentials.id,
+            name: credentials.name.to_owned(),
         });
 
         self.touch().await;
@@ -342,69 +954,296 @@ impl Lobby {
         Ok(())
     }
 
-    pub async fn kick(&self, own_id: &Uuid, player_id: &Uuid) -> Result<()> {
-        if self.is_host(own_id).await && own_id != player_id {
-            self.remove_player(player_id, Some(PrivateServerEvent::Kick))
-                .await;
-        } else {
+    /// Turns a spectator into a player (host only, while the lobby is open)
+    pub async fn promote_spectator(&self, own_id: &Uuid, spectator_id: &Uuid) -> Result<()> {
+        if !self.is_host(own_id).await || !self.has_phase(GamePhase::LobbyOpen).await {
             return Err(Error::Unauthorized);
         }
 
-        Ok(())
-    }
+        let (player_id, player_info) = {
+            let mut guard = self.state.write().await;
+            if guard.player_count() >= guard.settings.max_players as usize {
+                return Err(Error::LobbyFull);
+            }
+            let spectator = guard
+                .spectators
+                .remove(spectator_id)
+                .ok_or(Error::Unauthorized)?;
+
+            let player_info = PlayerInfo {
+                name: spectator.name,
+                is_host: false,
+                is_czar: false,
+                points: 0,
+                is_rando: false,
+                is_bot: false,
+                team: None,
+                is_afk: false,
+            };
+            guard.players.insert(
+                *spectator_id,
+                Player {
+                    secret: spectator.secret,
+                    info: player_info.clone(),
+                    cards: Vec::new(),
+                },
+            );
+            guard.czar_order.push_front(*spectator_id);
+            (*spectator_id, player_info)
+        };
 
-    pub async fn leave(&self, own_id: &Uuid) -> Result<()> {
-        self.remove_player(own_id, None).await;
+        self.emit_global(ServerEvent::SpectatorRemove {
+            spectator_id: player_id,
+        });
+        self.emit_global(ServerEvent::PlayerJoin {
+            player_id,
+            player_info,
+        });
+        self.send_lobby_state(&player_id).await;
 
         Ok(())
     }
 
-    pub async fn player_disconnected(self: &Arc<Lobby>, player_id: Uuid) {
-        // Make sure the player didn't got kicked, removed by anything beforehand or is in the disconnecting phase
-        let still_present = {
-            let guard = self.state.read().await;
-            guard.players.contains_key(&player_id)
-                && !self.disconnect_timers.contains_key(&player_id)
+    /// Use to remove a spectator from the lobby
+    async fn remove_spectator(&self, spectator_id: &Uuid, event: Option<PrivateServerEvent>) {
+        let removed = {
+            let mut guard = self.state.write().await;
+            guard.chat_times.remove(spectator_id);
+            guard.spectators.remove(spectator_id).is_some()
         };
-        if !still_present {
+        if !removed {
             return;
         }
 
-        let lobby = self.clone();
-        let handle = tokio::spawn(async move {
-            // Wait the grace period
-            tokio::time::sleep(GRACE_PERIOD).await;
-            // If player hasn't reconnected, remove them
-            lobby
-                .remove_player(&player_id, Some(PrivateServerEvent::Timeout))
-                .await;
+        self.emit_global(ServerEvent::SpectatorRemove {
+            spectator_id: *spectator_id,
         });
-        self.disconnect_timers.insert(player_id, handle);
+        if let Some(event) = event {
+            self.emit_private(spectator_id, event).await;
+        }
+        self.remove_private(spectator_id);
     }
 
-    /// Use to remove a player from the lobby
-    async fn remove_player(&self, player_id: &Uuid, event: Option<PrivateServerEvent>) {
-        let mut new_host_id: Option<Uuid> = None;
-        let in_game;
+    pub async fn is_spectator(&self, id: &Uuid) -> bool {
+        self.state.read().await.spectators.contains_key(id)
+    }
 
-        // Also remove the player from the disconnect_timers
-        self.disconnect_timers.remove(player_id);
+    pub async fn kick(&self, own_id: &Uuid, player_id: &Uuid) -> Result<()> {
+        if self.is_host(own_id).await && self.is_spectator(player_id).await {
+            self.remove_spectator(player_id, Some(PrivateServerEvent::Kick))
+                .await;
+            return Ok(());
+        }
 
-        {
-            let mut guard = self.state.write().await;
-            let was_host = guard
-                .players
-                .get(player_id)
-                .map(|p| p.info.is_host)
-                .unwrap_or(false);
+        // Rando is only removed via the settings
+        if self.is_host(own_id).await && own_id != player_id && *player_id != RANDO_ID {
+            self.remove_player(player_id, Some(PrivateServerEvent::Kick))
+                .await;
+        } else {
+            return Err(Error::Unauthorized);
+        }
 
-            in_game = !matches!(guard.phase, GamePhase::LobbyOpen | GamePhase::GameOver);
-            guard.players.remove(player_id);
-            guard.czar_order.retain(|id| id != player_id);
+        Ok(())
+    }
 
-            if was_host {
-                if let Some((&new_id, new_player)) = guard.players.iter_mut().next() {
-                    new_player.info.is_host = true;
+    /// Add a bot player with the given strategy (host only)
+    pub async fn add_bot(self: &Arc<Self>, own_id: &Uuid, strategy: BotStrategy) -> Result<()> {
+        if !self.is_host(own_id).await || !self.has_phase(GamePhase::LobbyOpen).await {
+            return Err(Error::Unauthorized);
+        }
+
+        let bot_id = Uuid::new_v4();
+        let player_info = {
+            let mut guard = self.state.write().await;
+            if guard.player_count() >= guard.settings.max_players as usize {
+                return Err(Error::LobbyFull);
+            }
+
+            let number = guard.players.values().filter(|p| p.info.is_bot).count() + 1;
+            let bot = Player::bot(format!("{} {}", strategy.name(), number));
+            let player_info = bot.info.clone();
+            guard.players.insert(bot_id, bot);
+            guard.czar_order.push_front(bot_id);
+            player_info
+        };
+
+        self.bots
+            .insert(bot_id, bot::spawn(self, bot_id, strategy.create()));
+
+        self.emit_global(ServerEvent::PlayerJoin {
+            player_id: bot_id,
+            player_info,
+        });
+        self.touch().await;
+
+        Ok(())
+    }
+
+    /// Remove a bot player (host only)
+    pub async fn remove_bot(&self, own_id: &Uuid, bot_id: &Uuid) -> Result<()> {
+        if !self.is_host(own_id).await || !self.bots.contains_key(bot_id) {
+            return Err(Error::Unauthorized);
+        }
+
+        self.remove_player(bot_id, None).await;
+
+        Ok(())
+    }
+
+    /// Sends a chat message from a player or spectator to everyone in the lobby
+    pub async fn chat(&self, own_id: &Uuid, text: String) -> Result<()> {
+        let text = text.trim().to_string();
+        if text.is_empty() || text.chars().count() > MAX_CHAT_LENGTH {
+            return Err(Error::Chat);
+        }
+
+        let message = {
+            let mut guard = self.state.write().await;
+            let name = guard.chat_name(own_id).ok_or(Error::Unauthorized)?;
+            if guard.muted.contains(own_id) {
+                return Err(Error::Chat);
+            }
+
+            let now = Instant::now();
+            let times = guard.chat_times.entry(*own_id).or_default();
+            times.retain(|&t| now.duration_since(t) < CHAT_RATE_WINDOW);
+            if times.len() >= CHAT_RATE_LIMIT {
+                return Err(Error::Chat);
+            }
+            times.push_back(now);
+
+            let message = ChatMessage {
+                player_id: *own_id,
+                name,
+                text,
+                timestamp: unix_millis(),
+            };
+            guard.chat.push_back(message.clone());
+            if guard.chat.len() > CHAT_HISTORY {
+                guard.chat.pop_front();
+            }
+            message
+        };
+
+        self.emit_global(ServerEvent::ChatMessage(message));
+        self.touch().await;
+
+        Ok(())
+    }
+
+    /// (Un)mutes a player or spectator in the chat (host only)
+    pub async fn mute(&self, own_id: &Uuid, player_id: &Uuid, muted: bool) -> Result<()> {
+        if !self.is_host(own_id).await || own_id == player_id {
+            return Err(Error::Unauthorized);
+        }
+
+        let changed = {
+            let mut guard = self.state.write().await;
+            if guard.chat_name(player_id).is_none() {
+                return Err(Error::Unauthorized);
+            }
+            if muted {
+                guard.muted.insert(*player_id)
+            } else {
+                guard.muted.remove(player_id)
+            }
+        };
+
+        if changed {
+            self.emit_global(ServerEvent::PlayerMute {
+                player_id: *player_id,
+                muted,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub async fn leave(&self, own_id: &Uuid) -> Result<()> {
+        if self.is_spectator(own_id).await {
+            self.remove_spectator(own_id, None).await;
+        } else {
+            self.remove_player(own_id, None).await;
+        }
+
+        Ok(())
+    }
+
+    pub async fn player_disconnected(self: &Arc<Lobby>, player_id: Uuid) {
+        // Spectators have nothing to lose, they can simply watch again
+        if self.is_spectator(&player_id).await {
+            self.remove_spectator(&player_id, None).await;
+            return;
+        }
+
+        // Make sure the player didn't got kicked, removed by anything beforehand or is in the disconnecting phase
+        let still_present = {
+            let guard = self.state.read().await;
+            (guard.players.contains_key(&player_id)
+                || guard.pending.iter().any(|(id, _)| *id == player_id))
+                && !self.disconnect_timers.contains_key(&player_id)
+        };
+        if !still_present {
+            return;
+        }
+
+        let lobby = self.clone();
+        let handle = tokio::spawn(async move {
+            // Wait the grace period
+            tokio::time::sleep(GRACE_PERIOD).await;
+            // If player hasn't reconnected, remove them
+            lobby
+                .remove_player(&player_id, Some(PrivateServerEvent::Timeout))
+                .await;
+        });
+        self.disconnect_timers.insert(player_id, handle);
+    }
+
+    /// Use to remove a player from the lobby
+    async fn remove_player(&self, player_id: &Uuid, event: Option<PrivateServerEvent>) {
+        let mut new_host_id: Option<Uuid> = None;
+        let in_game;
+        let has_humans;
+        let enough_players;
+        let mut removed_submission = None;
+
+        // Also remove the player from the disconnect_timers
+        self.disconnect_timers.remove(player_id);
+        if let Some((_, handle)) = self.bots.remove(player_id) {
+            handle.abort();
+        }
+
+        {
+            let mut guard = self.state.write().await;
+            let was_host = guard
+                .players
+                .get(player_id)
+                .map(|p| p.info.is_host)
+                .unwrap_or(false);
+
+            // Pending players didn't play yet, so the game can go on without them
+            let pending = guard.pending.len();
+            guard.pending.retain(|(id, _)| id != player_id);
+            let was_pending = guard.pending.len() != pending;
+
+            in_game =
+                !was_pending && !matches!(guard.phase, GamePhase::LobbyOpen | GamePhase::GameOver);
+            if in_game {
+                removed_submission = guard.drop_from_round(player_id);
+            }
+            guard.players.remove(player_id);
+            guard.czar_order.retain(|id| id != player_id);
+            guard.chat_times.remove(player_id);
+            has_humans = guard.has_humans();
+            enough_players = guard.player_count() >= guard.settings.min_players as usize;
+
+            if was_host {
+                if let Some((&new_id, new_player)) = guard
+                    .players
+                    .iter_mut()
+                    .find(|(_, p)| !p.info.is_rando && !p.info.is_bot)
+                {
+                    new_player.info.is_host = true;
                     new_host_id = Some(new_id);
                 }
             }
@@ -422,17 +1261,27 @@ impl Lobby {
 
         self.remove_private(player_id);
 
-        // If a player was removed during a game, abort the game and end it for everyone
-        if in_game {
-            self.cancel_game().await;
+        if let Some(index) = removed_submission {
+            self.emit_global(ServerEvent::SubmissionRemoved { index });
+        }
+
+        // The game goes on without the player, unless too few are left
+        if in_game && (!enough_players || !has_humans) {
+            self.cancel_game(GameOverReason::PlayerLeft).await;
+        } else if in_game {
+            // Waiting phases have to re-check with one player less
+            self.submission_notify.notify_one();
+            self.czar_notify.notify_one();
+        } else if !has_humans {
+            // Bots shouldn't keep playing on their own
+            self.cancel_task().await;
         }
     }
 
     /// Cancels the current game abruptly
-    async fn cancel_game(&self) {
+    async fn cancel_game(&self, reason: GameOverReason) {
         self.cancel_task().await;
-        self.set_phase_and_emit(GamePhase::GameOver, ServerEvent::GameOver)
-            .await;
+        self.game_over(reason).await;
     }
 
     /// Used for ending the current game
@@ -443,7 +1292,7 @@ impl Lobby {
         };
 
         if is_allowed {
-            self.cancel_game().await;
+            self.cancel_game(GameOverReason::HostEnded).await;
         } else {
             return Err(Error::Unauthorized);
         }
@@ -452,18 +1301,59 @@ impl Lobby {
     }
 
     /// Update settings (host only)
-    pub async fn update_settings(&self, player_id: &Uuid, new_settings: Settings) -> Result<()> {
+    pub async fn update_settings(
+        &self,
+        player_id: &Uuid,
+        mut new_settings: Settings,
+    ) -> Result<()> {
         if self.is_host(player_id).await && self.has_phase(GamePhase::LobbyOpen).await {
+            let previous = { self.state.read().await.settings.clone() };
+
+            // only trust the deck infos known to the server, clients just toggle them.
+            // decks hidden by the old language filter have to be listed again
+            if previous.language != new_settings.language
+                || previous.only_matching_languages != new_settings.only_matching_languages
+            {
+                new_settings.decks =
+                    Deck::get_all_cached_info(&self.cache, Some(new_settings.decks)).await?;
+            } else {
+                let mut decks = previous.decks.clone();
+                Deck::apply_enabled(&mut decks, &new_settings.decks);
+                new_settings.decks = decks;
+            }
+            self.arrange_decks(&mut new_settings);
+
+            new_settings.validate()?;
+
+            let nsfw_enabled = new_settings.decks.iter().any(|d| d.enabled && d.meta.nsfw);
+            if (new_settings.allow_nsfw && !self.allow_nsfw)
+                || (nsfw_enabled && !new_settings.allow_nsfw)
+            {
+                return Err(Error::Nsfw);
+            }
+
+            let mismatched: Vec<String> = new_settings
+                .decks
+                .iter()
+                .filter(|d| d.enabled && !new_settings.matches_language(&d.meta))
+                .filter(|d| {
+                    !previous
+                        .decks
+                        .iter()
+                        .any(|p| p.enabled && p.meta.deckcode == d.meta.deckcode)
+                })
+                .map(|d| d.meta.deckcode.clone())
+                .collect();
+
             let to_remove: Vec<Uuid> = {
                 let guard = self.state.read().await;
                 let excess = guard
-                    .players
-                    .len()
+                    .player_count()
                     .saturating_sub(new_settings.max_players as usize);
                 guard
                     .players
                     .keys()
-                    .filter(|&&id| id != *player_id) // never kick out the current player
+                    .filter(|&&id| id != *player_id && id != RANDO_ID) // never kick out the current player
                     .take(excess)
                     .cloned()
                     .collect()
@@ -474,14 +1364,57 @@ impl Lobby {
                     .await;
             }
 
-            {
+            let (rando_changed, teamless) = {
                 let mut guard = self.state.write().await;
                 guard.settings = new_settings.clone();
-            }
 
+                // Players of removed teams become teamless
+                let team_count = new_settings.team_count();
+                let mut teamless = Vec::new();
+                for (&id, p) in guard.players.iter_mut() {
+                    if p.info.team.is_some_and(|t| t >= team_count) {
+                        p.info.team = None;
+                        teamless.push(id);
+                    }
+                }
+
+                let has_rando = guard.players.contains_key(&RANDO_ID);
+                let rando_changed = if new_settings.rando_cardrissian && !has_rando {
+                    let rando = Player::rando();
+                    let info = rando.info.clone();
+                    guard.players.insert(RANDO_ID, rando);
+                    Some(ServerEvent::PlayerJoin {
+                        player_id: RANDO_ID,
+                        player_info: info,
+                    })
+                } else if !new_settings.rando_cardrissian && has_rando {
+                    guard.players.remove(&RANDO_ID);
+                    Some(ServerEvent::PlayerRemove {
+                        player_id: RANDO_ID,
+                    })
+                } else {
+                    None
+                };
+                (rando_changed, teamless)
+            };
+
+            if let Some(event) = rando_changed {
+                self.emit_global(event);
+            }
+            for player_id in teamless {
+                self.emit_global(ServerEvent::TeamJoin {
+                    player_id,
+                    team: None,
+                });
+            }
             self.emit_global(ServerEvent::UpdateSettings {
                 settings: new_settings,
             });
+            if !mismatched.is_empty() {
+                self.emit_global(ServerEvent::LanguageMismatch {
+                    deckcodes: mismatched,
+                });
+            }
 
             Ok(())
         } else {
@@ -492,6 +1425,9 @@ impl Lobby {
     pub async fn add_deck(&self, player_id: &Uuid, deckcode: String) -> Result<()> {
         if self.is_host(player_id).await && self.has_phase(GamePhase::LobbyOpen).await {
             let fetched = Deck::fetch(&deckcode).await?;
+            if fetched.meta.nsfw && !self.allow_nsfw {
+                return Err(Error::Nsfw);
+            }
             fetched.save(&self.cache).await?;
 
             self.update_decks(|settings| async {
@@ -527,10 +1463,12 @@ impl Lobby {
         let decks = p(settings).await?;
 
         // update settings
-        {
+        let decks = {
             let mut guard = self.state.write().await;
-            guard.settings.decks = decks.clone();
-        }
+            guard.settings.decks = decks;
+            self.arrange_decks(&mut guard.settings);
+            guard.settings.decks.clone()
+        };
 
         // send update
         self.touch().await;
@@ -539,6 +1477,14 @@ impl Lobby {
         Ok(())
     }
 
+    /// Hides decks forbidden on this server and arranges the rest by language
+    fn arrange_decks(&self, settings: &mut Settings) {
+        if !self.allow_nsfw {
+            settings.decks.retain(|d| !d.meta.nsfw);
+        }
+        settings.arrange_decks();
+    }
+
     /// Assigns the task handle to the game task
     pub async fn assign_task(&self, handle: JoinHandle<()>) {
         *self.game_task.write().await = Some(handle);
@@ -557,25 +1503,40 @@ impl Lobby {
             return Err(Error::Unauthorized);
         }
 
-        if !self.has_phase(GamePhase::LobbyOpen).await
-            || !self.min_players().await
-            || !self.min_decks().await?
-        {
-            return Err(Error::LobbyStart);
+        self.check_start_requirements().await?;
+
+        let assigned = {
+            let mut guard = self.state.write().await;
+            let assigned = guard.balance_teams();
+            if guard.settings.team_count() > 0 && guard.filled_teams() < 2 {
+                return Err(Error::LobbyStart(StartRequirement::Teams));
+            }
+            assigned
+        };
+        for (player_id, team) in assigned {
+            self.emit_global(ServerEvent::TeamJoin {
+                player_id,
+                team: Some(team),
+            });
         }
 
         // make sure no other task is running
         let mut slot = self.game_task.write().await;
         if let Some(h) = slot.as_ref() {
             if !h.is_finished() {
-                return Err(Error::LobbyStart);
+                return Err(Error::LobbyStart(StartRequirement::LobbyOpen));
             }
         }
 
         let lobby = self.clone();
         let handle = tokio::spawn(async move {
-            if let Err(e) = Lobby::run_game(lobby).await {
+            if let Err(e) = Lobby::run_game(lobby.clone()).await {
                 error!("Game loop exited with error: {:?}", e);
+                let reason = match e {
+                    Error::Deck(_) => GameOverReason::NotEnoughCards,
+                    _ => GameOverReason::InternalError,
+                };
+                lobby.game_over(reason).await;
             }
         });
         *slot = Some(handle);
@@ -585,43 +1546,116 @@ impl Lobby {
 
     /// Main game loop
     pub async fn run_game(self: Arc<Self>) -> Result<()> {
+        {
+            let mut guard = self.state.write().await;
+            guard.timer = PhaseTimer::default();
+            guard.team_points = vec![0; guard.settings.team_count()];
+            guard.czar_team = None;
+            guard.czar_rounds.clear();
+            guard.last_winner = None;
+            guard.seed = rand::rng().random();
+            guard.discard.clear();
+            guard.rounds_won.clear();
+            guard.sudden_death.clear();
+            guard.sudden_death_rounds = 0;
+            guard.winners.clear();
+            guard.standings.clear();
+            guard.team_standings.clear();
+        }
         self.reset_round().await?;
 
-        loop {
+        let reason = loop {
             self.increment_round().await;
 
             self.assign_czar().await?;
             self.submitting().await;
 
-            let no_subs = { self.state.read().await.submissions.is_empty() };
-            if no_subs {
+            let (no_subs, game_mode, skipped) = {
+                let guard = self.state.read().await;
+                (
+                    guard.submissions.is_empty(),
+                    guard.settings.game_mode,
+                    guard.round_skipped,
+                )
+            };
+            if no_subs || skipped {
                 self.emit_global(ServerEvent::RoundSkip);
+            } else if game_mode == GameMode::Voting {
+                self.voting().await;
             } else {
                 self.judging().await;
             }
 
+            self.track_afk().await;
+
             // wait the normal time
             self.wait_time_secs().await;
 
-            let done = {
+            let end_reason = {
                 let guard = self.state.read().await;
-                guard.settings.end_condition_reached(&guard)
+                guard.settings.end_reason(&guard)
             };
-            if done {
-                break;
+            if let Some(reason) = end_reason {
+                let contenders = {
+                    let mut guard = self.state.write().await;
+                    let leaders = guard.leaders();
+                    if guard.settings.tie_break == TieBreak::SuddenDeath
+                        && leaders.len() > 1
+                        && guard.sudden_death_rounds < MAX_SUDDEN_DEATH_ROUNDS
+                    {
+                        guard.sudden_death_rounds += 1;
+                        guard.sudden_death = leaders.into_iter().flatten().collect();
+                        Some(guard.sudden_death.clone())
+                    } else {
+                        None
+                    }
+                };
+                let Some(player_ids) = contenders else {
+                    break reason;
+                };
+                self.emit_global(ServerEvent::SuddenDeath { player_ids });
             }
 
             self.reset_round().await?;
-        }
+        };
 
-        self.set_phase_and_emit(GamePhase::GameOver, ServerEvent::GameOver)
-            .await;
+        self.game_over(reason).await;
         Ok(())
     }
 
+    /// Finishes the game by naming the winners and final standings
+    async fn game_over(&self, reason: GameOverReason) {
+        let (winners, standings, team_standings) = {
+            let mut guard = self.state.write().await;
+            guard.sudden_death.clear();
+            // A paused game doesn't leave the lobby paused
+            guard.timer = PhaseTimer::default();
+            guard.winners = guard.winners();
+            guard.standings = guard.standings();
+            guard.team_standings = guard.team_standings();
+            guard.game_over_reason = Some(reason);
+            (
+                guard.winners.clone(),
+                guard.standings.clone(),
+                guard.team_standings.clone(),
+            )
+        };
+
+        self.set_phase_and_emit(
+            GamePhase::GameOver,
+            ServerEvent::GameOver {
+                reason,
+                winners,
+                standings,
+                team_standings,
+            },
+        )
+        .await;
+    }
+
     /// Refill cards and clear state
     async fn reset_round(&self) -> Result<()> {
-        {
+        let seated = {
             let mut guard = self.state.write().await;
 
             // Remove player cards (from the submitted_by_player map)
@@ -642,7 +1676,25 @@ impl Lobby {
             // Clear everything
             guard.submissions.clear();
             guard.czar_pick = None;
+            guard.czar_ranking.clear();
+            guard.placements.clear();
+            guard.votes.clear();
             guard.black_card = None;
+            guard.black_card_redraws = 0;
+            guard.round_skipped = false;
+            guard.mulligans.clear();
+            guard.fallback_vote = false;
+            guard.czar_timed_out = false;
+            guard.redrawing = false;
+
+            guard.seat_pending()
+        };
+
+        for (player_id, player_info) in seated {
+            self.emit_global(ServerEvent::PlayerJoin {
+                player_id,
+                player_info,
+            });
         }
 
         self.fill_white_cards().await?;
@@ -654,43 +1706,217 @@ impl Lobby {
     async fn wait_time_secs(&self) {
         let wait_time_secs = self.state.read().await.settings.wait_time_secs;
         if let Some(secs) = wait_time_secs {
-            sleep(Duration::from_secs(secs)).await;
+            let never = Notify::new();
+            self.wait_phase(Some(Duration::from_secs(secs)), &never, |_| false)
+                .await;
         }
     }
 
-    /// Assigns the czar and deals black card
-    async fn assign_czar(&self) -> Result<()> {
-        let next = {
+    /// Waits until `done` or the phase timer runs out, `None` waits without a timer.
+    /// The timer is frozen while the game is paused.
+    async fn wait_phase(
+        &self,
+        duration: Option<Duration>,
+        notify: &Notify,
+        done: fn(&LobbyData) -> bool,
+    ) {
+        let deadline = {
             let mut guard = self.state.write().await;
-            guard.czar_order.pop_back()
+            guard.timer.start(duration);
+            guard.timer.deadline_ms()
         };
+        self.emit_global(ServerEvent::PhaseDeadline { deadline });
 
-        if let Some(player_id) = next {
-            {
-                {
-                    let mut guard = self.state.write().await;
-                    guard
-                        .players
-                        .values_mut()
-                        .for_each(|p| p.info.is_czar = false);
-                    if let Some(p) = guard.players.get_mut(&player_id) {
-                        p.info.is_czar = true;
-                    }
+        loop {
+            let deadline = {
+                let guard = self.state.read().await;
+                if done(&guard) {
+                    break;
                 }
+                guard.timer.deadline
+            };
+            tokio::select! {
+                _ = sleep_until(deadline) => break,
+                _ = notify.notified() => {}
+                _ = self.timer_notify.notified() => {}
+            }
+        }
 
-                let black_card = self.fill_black_card().await?;
+        self.state.write().await.timer.stop();
+    }
 
-                self.emit_global(ServerEvent::StartRound {
-                    czar_id: player_id,
-                    black_card,
-                });
+    /// Host freezes the phase timer
+    pub async fn pause(&self, player_id: &Uuid) -> Result<()> {
+        if !self.is_host(player_id).await {
+            return Err(Error::Unauthorized);
+        }
+
+        let remaining = {
+            let mut guard = self.state.write().await;
+            if guard.timer.paused
+                || matches!(guard.phase, GamePhase::LobbyOpen | GamePhase::GameOver)
+            {
+                return Err(Error::Pause);
             }
+            guard.timer.pause()
+        };
+
+        self.timer_notify.notify_one();
+        self.emit_global(ServerEvent::Paused {
+            remaining_secs: remaining.map(|r| r.as_secs()),
+        });
+
+        Ok(())
+    }
+
+    /// Host lets the phase timer continue
+    pub async fn resume(&self, player_id: &Uuid) -> Result<()> {
+        if !self.is_host(player_id).await {
+            return Err(Error::Unauthorized);
+        }
+
+        let (remaining, deadline) = {
+            let mut guard = self.state.write().await;
+            if !guard.timer.paused {
+                return Err(Error::Pause);
+            }
+            (guard.timer.resume(), guard.timer.deadline_ms())
+        };
+
+        self.timer_notify.notify_one();
+        self.emit_global(ServerEvent::Resumed {
+            remaining_secs: remaining.map(|r| r.as_secs()),
+        });
+        self.emit_global(ServerEvent::PhaseDeadline { deadline });
+
+        Ok(())
+    }
+
+    /// Host voids the current round, e.g. when the czar went afk
+    pub async fn skip_round(&self, player_id: &Uuid) -> Result<()> {
+        if !self.is_host(player_id).await {
+            return Err(Error::Unauthorized);
+        }
+
+        {
+            let mut guard = self.state.write().await;
+            if !matches!(guard.phase, GamePhase::Submitting | GamePhase::Judging)
+                || guard.round_skipped
+            {
+                return Err(Error::SkipRound);
+            }
+            guard.skip_round();
+        }
+
+        // The waiting phase ends and announces the skip
+        self.submission_notify.notify_one();
+        self.czar_notify.notify_one();
+
+        Ok(())
+    }
+
+    /// Host moves a player to the front of the czar queue
+    pub async fn force_czar(&self, own_id: &Uuid, player_id: &Uuid) -> Result<()> {
+        if !self.is_host(own_id).await {
+            return Err(Error::Unauthorized);
+        }
+
+        {
+            let mut guard = self.state.write().await;
+            let eligible = guard
+                .players
+                .get(player_id)
+                .is_some_and(|p| !p.info.is_rando);
+            if !eligible
+                || guard.settings.game_mode == GameMode::Voting
+                || guard.phase == GamePhase::GameOver
+            {
+                return Err(Error::Unauthorized);
+            }
+            guard.forced_czar = Some(*player_id);
+        }
+
+        self.emit_global(ServerEvent::NextCzar {
+            player_id: *player_id,
+        });
+
+        Ok(())
+    }
+
+    /// Player picks a team while the lobby is open
+    pub async fn join_team(&self, player_id: &Uuid, team: Option<usize>) -> Result<()> {
+        {
+            let mut guard = self.state.write().await;
+            let team_count = guard.settings.team_count();
+            if guard.phase != GamePhase::LobbyOpen || team.is_some_and(|t| t >= team_count) {
+                return Err(Error::Team);
+            }
+            let player = guard
+                .players
+                .get_mut(player_id)
+                .ok_or(Error::Unauthorized)?;
+            player.info.team = team;
+        }
+
+        self.emit_global(ServerEvent::TeamJoin {
+            player_id: *player_id,
+            team,
+        });
+
+        Ok(())
+    }
+
+    /// Assigns the czar and deals black card
+    async fn assign_czar(&self) -> Result<()> {
+        let next = {
+            let mut guard = self.state.write().await;
+            if guard.settings.game_mode == GameMode::Voting {
+                guard
+                    .players
+                    .values_mut()
+                    .for_each(|p| p.info.is_czar = false);
+                None
+            } else {
+                // Reproducible for the same seed and round
+                let mut rng = StdRng::seed_from_u64(guard.seed ^ u64::from(guard.round));
+                guard.next_czar(&mut rng)
+            }
+        };
+
+        let Some(player_id) = next else {
+            // Nobody judges in the voting mode
+            let black_card = self.fill_black_card().await?;
+            self.emit_global(ServerEvent::StartRound {
+                czar_id: None,
+                black_card,
+            });
+            return Ok(());
+        };
 
-            // re-queue
+        {
             {
                 let mut guard = self.state.write().await;
-                guard.czar_order.push_front(player_id);
+                guard
+                    .players
+                    .values_mut()
+                    .for_each(|p| p.info.is_czar = false);
+                if let Some(p) = guard.players.get_mut(&player_id) {
+                    p.info.is_czar = true;
+                }
             }
+
+            let black_card = self.fill_black_card().await?;
+
+            self.emit_global(ServerEvent::StartRound {
+                czar_id: Some(player_id),
+                black_card,
+            });
+        }
+
+        // re-queue
+        {
+            let mut guard = self.state.write().await;
+            guard.czar_order.push_front(player_id);
         }
         Ok(())
     }
@@ -698,6 +1924,7 @@ impl Lobby {
     /// Collect submissions
     async fn submitting(&self) {
         self.set_phase(GamePhase::Submitting).await;
+        self.submit_rando().await;
 
         let (max_submitting_time_secs, player_count) = {
             let guard = self.state.read().await;
@@ -706,30 +1933,175 @@ impl Lobby {
                 guard.players.len(),
             )
         };
-        if let Some(max) = max_submitting_time_secs {
-            let timeout = sleep(Duration::from_secs(max.to_seconds(player_count as u64)));
-            tokio::pin!(timeout);
+        let duration = max_submitting_time_secs
+            .map(|max| Duration::from_secs(max.to_seconds(player_count as u64)));
+        self.wait_phase(duration, &self.submission_notify, |data| {
+            data.all_player_submitted() || data.round_skipped
+        })
+        .await;
+        self.auto_submit().await;
 
-            loop {
-                if self.all_player_submitted().await {
-                    break;
-                }
-                tokio::select! {
-                    _ = &mut timeout => break,
-                    _ = self.submission_notify.notified() => {}
+        // now shuffle the submission array
+        {
+            let mut guard = self.state.write().await;
+            let mut rng = rand::rng();
+            guard.submissions.shuffle_together(&mut rng);
+        }
+    }
+
+    /// Submits random cards for everyone who missed the deadline, if enabled
+    async fn auto_submit(&self) {
+        let missing: Vec<(Uuid, Vec<usize>)> = {
+            let guard = self.state.read().await;
+            if !guard.settings.auto_submit || guard.round_skipped {
+                return;
+            }
+            let fields = guard
+                .black_card
+                .as_ref()
+                .map(|b| b.fields)
+                .unwrap_or_default();
+            let mut rng = rand::rng();
+            guard
+                .players
+                .iter()
+                .filter(|(id, p)| {
+                    !p.info.is_czar
+                        && !guard.sits_out(id)
+                        && !guard.submissions.submitted_by_player.contains_key(id)
+                        && p.cards.len() >= fields
+                })
+                .map(|(&id, p)| {
+                    let indexes = rand::seq::index::sample(&mut rng, p.cards.len(), fields);
+                    (id, indexes.into_vec())
+                })
+                .collect()
+        };
+
+        for (player_id, indexes) in missing {
+            if let Err(e) = self.submit(&player_id, indexes, true).await {
+                error!("Couldn't auto-submit for {}: {:?}", player_id, e);
+            }
+        }
+    }
+
+    /// Counts the missed submissions and czar picks of this round,
+    /// players are marked as AFK (or removed) after `afk_rounds` misses in a row
+    async fn track_afk(self: &Arc<Self>) {
+        let (afk, kick) = {
+            let mut guard = self.state.write().await;
+            let Some(afk_rounds) = guard.settings.afk_rounds else {
+                return;
+            };
+            // Voided rounds are nobody's fault
+            if guard.round_skipped {
+                return;
+            }
+
+            // The fallbacks may have picked a winner in the czar's place
+            let czar_missed = guard.czar_timed_out;
+            let (missed, acted): (Vec<Uuid>, Vec<Uuid>) = guard
+                .players
+                .iter()
+                .filter(|(id, p)| !p.info.is_rando && !p.info.is_bot && !guard.sits_out(id))
+                .map(|(&id, _)| id)
+                .partition(|id| {
+                    if guard.is_czar(id) {
+                        czar_missed
+                    } else {
+                        !guard.submissions.submitted_by_player.contains_key(id)
+                            || guard.submissions.auto_submitted.contains(id)
+                    }
+                });
+
+            for player_id in acted {
+                guard.missed.remove(&player_id);
+            }
+            let mut afk = Vec::new();
+            for player_id in missed {
+                let count = guard.missed.entry(player_id).or_default();
+                *count += 1;
+                if *count >= afk_rounds {
+                    afk.push(player_id);
                 }
             }
-        } else {
-            while !self.all_player_submitted().await {
-                self.submission_notify.notified().await;
+            let kick = guard.settings.kick_afk;
+            if !kick {
+                afk.retain(|id| match guard.players.get_mut(id) {
+                    Some(p) if !p.info.is_afk => {
+                        p.info.is_afk = true;
+                        true
+                    }
+                    _ => false,
+                });
+            }
+            (afk, kick)
+        };
+
+        for player_id in afk {
+            if kick {
+                // Removing could end the game, which aborts this task
+                let lobby = self.clone();
+                tokio::spawn(async move {
+                    lobby
+                        .remove_player(&player_id, Some(PrivateServerEvent::Timeout))
+                        .await;
+                });
+            } else {
+                self.emit_global(ServerEvent::PlayerAfk {
+                    player_id,
+                    is_afk: true,
+                });
             }
         }
+    }
 
-        // now shuffle the submission array
-        {
+    /// The player acted, so they aren't AFK anymore
+    async fn mark_active(&self, player_id: &Uuid) {
+        let was_afk = {
             let mut guard = self.state.write().await;
-            let mut rng = rand::rng();
-            guard.submissions.shuffle_together(&mut rng);
+            guard.missed.remove(player_id);
+            match guard.players.get_mut(player_id) {
+                Some(p) if p.info.is_afk => {
+                    p.info.is_afk = false;
+                    true
+                }
+                _ => false,
+            }
+        };
+
+        if was_afk {
+            self.emit_global(ServerEvent::PlayerAfk {
+                player_id: *player_id,
+                is_afk: false,
+            });
+        }
+    }
+
+    /// Rando Cardrissian submits random cards from his hand, if he plays along
+    async fn submit_rando(&self) {
+        let indexes = {
+            let guard = self.state.read().await;
+            let fields = guard
+                .black_card
+                .as_ref()
+                .map(|b| b.fields)
+                .unwrap_or_default();
+            // Rando doesn't play sudden deaths he isn't tied in
+            if guard.sits_out(&RANDO_ID) {
+                return;
+            }
+            match guard.players.get(&RANDO_ID) {
+                Some(rando) if rando.cards.len() >= fields => {
+                    let mut rng = rand::rng();
+                    rand::seq::index::sample(&mut rng, rando.cards.len(), fields).into_vec()
+                }
+                _ => return,
+            }
+        };
+
+        if let Err(e) = self.submit_cards(&RANDO_ID, indexes).await {
+            error!("Rando couldn't submit: {:?}", e);
         }
     }
 
@@ -752,53 +2124,193 @@ impl Lobby {
             let guard = self.state.read().await;
             guard.settings.max_judging_time_secs
         };
-        if let Some(max) = max_judging_time_secs {
-            let timeout = sleep(Duration::from_secs(max));
-            tokio::pin!(timeout);
+        self.wait_phase(
+            max_judging_time_secs.map(Duration::from_secs),
+            &self.czar_notify,
+            |data| data.czar_submitted() || data.round_skipped,
+        )
+        .await;
 
-            loop {
-                if self.czar_submitted().await {
-                    break;
-                }
-                tokio::select! {
-                    _ = &mut timeout => break,
-                    _ = self.czar_notify.notified() => {}
+        if self.round_skipped().await {
+            self.emit_global(ServerEvent::RoundSkip);
+            return;
+        }
+
+        // award
+        let (placements, czar_timeout) = {
+            let guard = self.state.read().await;
+            let placements: Vec<(usize, u32)> = guard
+                .czar_ranking
+                .iter()
+                .enumerate()
+                .map(|(place, &index)| (index, guard.settings.points_for_place(place)))
+                .collect();
+            (placements, guard.settings.czar_timeout)
+        };
+        if !placements.is_empty() {
+            self.award(placements, false).await;
+            return;
+        }
+
+        // the czar didn't pick in time
+        self.state.write().await.czar_timed_out = true;
+        match czar_timeout {
+            CzarTimeout::Skip => self.emit_global(ServerEvent::RoundSkip),
+            CzarTimeout::Random => {
+                let (count, points) = {
+                    let guard = self.state.read().await;
+                    (guard.submissions.len(), guard.settings.points_for_place(0))
+                };
+                if count == 0 {
+                    self.emit_global(ServerEvent::RoundSkip);
+                } else {
+                    let index = rand::rng().random_range(0..count);
+                    self.award(vec![(index, points)], true).await;
                 }
             }
-        } else {
-            while !self.czar_submitted().await {
-                self.czar_notify.notified().await;
+            CzarTimeout::Vote => {
+                self.state.write().await.fallback_vote = true;
+                self.emit_global(ServerEvent::CzarVote);
+                self.collect_votes(Some(FALLBACK_VOTE_SECS)).await;
+                if self.round_skipped().await {
+                    self.emit_global(ServerEvent::RoundSkip);
+                } else {
+                    self.tally_votes(true).await;
+                }
             }
         }
+    }
 
-        // award
-        let czar_pick = {
+    /// Players vote for the winner instead of a czar
+    async fn voting(&self) {
+        let cards = {
             let guard = self.state.read().await;
-            guard.czar_pick
+            guard.submissions.reveal.clone()
         };
-        if let Some(index) = czar_pick {
-            if let Some(winner_id) = {
-                let guard = self.state.read().await;
-                guard.submissions.by_index.get(index).copied()
-            } {
-                {
-                    let mut guard = self.state.write().await;
-                    if let Some(p) = guard.players.get_mut(&winner_id) {
-                        p.info.points += 1;
-                    }
+
+        self.set_phase_and_emit(
+            GamePhase::Judging,
+            ServerEvent::RevealCards {
+                selected_cards: cards,
+            },
+        )
+        .await;
+
+        let max_judging_time_secs = {
+            let guard = self.state.read().await;
+            guard.settings.max_judging_time_secs
+        };
+        self.collect_votes(max_judging_time_secs).await;
+
+        if self.round_skipped().await {
+            self.emit_global(ServerEvent::RoundSkip);
+            return;
+        }
+
+        self.tally_votes(false).await;
+    }
+
+    /// Waits until everyone voted or the time ran out
+    async fn collect_votes(&self, secs: Option<u64>) {
+        self.wait_phase(secs.map(Duration::from_secs), &self.czar_notify, |data| {
+            data.all_player_voted() || data.round_skipped
+        })
+        .await;
+    }
+
+    /// Awards the submissions with the most votes, `automatic` if the votes replaced the czar
+    async fn tally_votes(&self, automatic: bool) {
+        let (votes, vote_tie) = {
+            let guard = self.state.read().await;
+            let mut votes = vec![0u32; guard.submissions.len()];
+            for &index in guard.votes.values() {
+                if let Some(count) = votes.get_mut(index) {
+                    *count += 1;
                 }
+            }
+            (votes, guard.settings.vote_tie)
+        };
+        self.emit_global(ServerEvent::VoteTally {
+            votes: votes.clone(),
+        });
 
-                self.set_phase_and_emit(
-                    GamePhase::RoundFinished,
-                    ServerEvent::RoundResult {
-                        player_id: winner_id,
-                        winning_card_index: index,
-                    },
-                )
-                .await;
+        let most = votes.iter().copied().max().unwrap_or_default();
+        let mut winners: Vec<usize> = (0..votes.len()).filter(|&i| votes[i] == most).collect();
+        if most == 0 {
+            winners.clear();
+        } else if winners.len() > 1 {
+            match vote_tie {
+                VoteTie::Shared => {}
+                VoteTie::Random => {
+                    let mut rng = rand::rng();
+                    winners.shuffle(&mut rng);
+                    winners.truncate(1);
+                }
+                VoteTie::Skip => winners.clear(),
             }
-        } else {
+        }
+
+        if winners.is_empty() {
             self.emit_global(ServerEvent::RoundSkip);
+            return;
+        }
+
+        self.award(
+            winners.into_iter().map(|index| (index, 1)).collect(),
+            automatic,
+        )
+        .await;
+    }
+
+    /// Awards the points for the `(card_index, points)` placements and finishes the round,
+    /// `automatic` if no czar picked them
+    async fn award(&self, placements: Vec<(usize, u32)>, automatic: bool) {
+        let placements: Vec<Placement> = {
+            let mut guard = self.state.write().await;
+            let mut awarded = Vec::with_capacity(placements.len());
+            for (card_index, points) in placements {
+                if let Some(player_id) = guard.submissions.by_index.get(card_index).copied() {
+                    let mut team = None;
+                    if let Some(p) = guard.players.get_mut(&player_id) {
+                        p.info.points += points;
+                        team = p.info.team;
+                    }
+                    if let Some(team_points) = team.and_then(|t| guard.team_points.get_mut(t)) {
+                        *team_points += points;
+                    }
+                    awarded.push(Placement {
+                        player_id,
+                        card_index,
+                        points,
+                        auto_submitted: guard.submissions.auto_submitted.contains(&player_id),
+                    });
+                }
+            }
+            guard.czar_pick = awarded.first().map(|p| p.card_index);
+            guard.last_winner = awarded.first().map(|p| p.player_id);
+
+            // Shared first places are won by everyone, only the czar's picks count
+            if !automatic && guard.settings.game_mode == GameMode::Czar {
+                let best = awarded.first().map(|p| p.points);
+                for placement in awarded.iter().filter(|p| Some(p.points) == best) {
+                    *guard.rounds_won.entry(placement.player_id).or_default() += 1;
+                }
+            }
+            guard.placements = awarded.clone();
+            awarded
+        };
+
+        if let Some(first) = placements.first() {
+            self.set_phase_and_emit(
+                GamePhase::RoundFinished,
+                ServerEvent::RoundResult {
+                    player_id: first.player_id,
+                    winning_card_index: first.card_index,
+                    placements: placements.clone(),
+                    automatic,
+                },
+            )
+            .await;
         }
     }
 
@@ -818,24 +2330,37 @@ impl Lobby {
 
     /// Fill players' white hands
     async fn fill_white_cards(&self) -> Result<()> {
-        let needs: Vec<(Uuid, usize)> = {
-            let guard = self.state.read().await;
-            guard
+        let (needs, trimmed, settings, discard) = {
+            let mut guard = self.state.write().await;
+            let hand_size = guard.settings.hand_size;
+
+            // A shrunk hand size drops the newest cards
+            let mut trimmed = Vec::new();
+            for (&id, p) in guard.players.iter_mut() {
+                if p.cards.len() > hand_size {
+                    p.cards.truncate(hand_size);
+                    trimmed.push(id);
+                }
+            }
+
+            let needs: Vec<(Uuid, usize)> = guard
                 .players
                 .iter()
-                .map(|(&id, p)| (id, 10usize.saturating_sub(p.cards.len())))
-                .collect()
-        };
-
-        let settings = {
-            let guard = self.state.read().await;
-            guard.settings.clone()
+                .map(|(&id, p)| (id, hand_size.saturating_sub(p.cards.len())))
+                .collect();
+            (
+                needs,
+                trimmed,
+                guard.settings.clone(),
+                guard.discard.clone(),
+            )
         };
 
         let mut deals: Vec<(Uuid, Vec<WhiteCard>)> = Vec::with_capacity(needs.len());
         for (player_id, count) in needs {
             if count > 0 {
-                let new_cards = WhiteCard::choose_random(&self.cache, count, &settings).await?;
+                let new_cards =
+                    WhiteCard::choose_random(&self.cache, count, &settings, &discard).await?;
                 deals.push((player_id, new_cards));
             }
         }
@@ -849,7 +2374,8 @@ impl Lobby {
             }
         }
 
-        for (player_id, _new_cards) in deals {
+        let changed = deals.into_iter().map(|(id, _)| id).chain(trimmed);
+        for player_id in changed {
             let hand = {
                 let guard = self.state.read().await;
                 guard.players.get(&player_id).map(|p| p.cards.clone())
@@ -863,8 +2389,66 @@ impl Lobby {
         Ok(())
     }
 
+    /// Player pays points to discard their hand and draw a new one
+    pub async fn mulligan(&self, player_id: &Uuid) -> Result<()> {
+        let (settings, avoid) = {
+            let guard = self.state.read().await;
+            guard.mulligan_allowed(player_id).ok_or(Error::Mulligan)?;
+            // The new hand shouldn't repeat the discarded or the current cards
+            let mut avoid = guard.discard.clone();
+            if let Some(player) = guard.players.get(player_id) {
+                avoid.extend(player.cards.iter().cloned());
+            }
+            (guard.settings.clone(), avoid)
+        };
+
+        // Draw first, so a failing draw doesn't cost anything
+        let cards =
+            WhiteCard::choose_random(&self.cache, settings.hand_size, &settings, &avoid).await?;
+
+        let (hand, points) = {
+            let mut guard = self.state.write().await;
+            // The state could have changed while drawing
+            let mulligan = guard.mulligan_allowed(player_id).ok_or(Error::Mulligan)?;
+            let used = guard.mulligans.get(player_id).copied().unwrap_or_default();
+
+            let player = guard.players.get_mut(player_id).ok_or(Error::Mulligan)?;
+            player.info.points -= mulligan.cost;
+            let team = player.info.team;
+            let old_cards = std::mem::replace(&mut player.cards, cards);
+            let hand = player.cards.clone();
+            let points = player.info.points;
+
+            if let Some(team_points) = team.and_then(|t| guard.team_points.get_mut(t)) {
+                *team_points = team_points.saturating_sub(mulligan.cost);
+            }
+            guard.discard.extend(old_cards);
+            guard.mulligans.insert(*player_id, used + 1);
+            (hand, points)
+        };
+
+        self.emit_global(ServerEvent::Mulligan {
+            player_id: *player_id,
+            points,
+        });
+        self.emit_private(player_id, PrivateServerEvent::UpdateHand { cards: hand })
+            .await;
+
+        Ok(())
+    }
+
     /// Submit white cards
     pub async fn submit_cards(&self, player_id: &Uuid, indexes: Vec<usize>) -> Result<()> {
+        self.submit(player_id, indexes, false).await
+    }
+
+    /// Submit white cards, `auto_submitted` if the server picked them for the player
+    async fn submit(
+        &self,
+        player_id: &Uuid,
+        indexes: Vec<usize>,
+        auto_submitted: bool,
+    ) -> Result<()> {
         // check for double indexes
         if !all_unique(&indexes) {
             return Err(Error::CardSubmission);
@@ -872,24 +2456,29 @@ impl Lobby {
 
         let in_phase = {
             let guard = self.state.read().await;
-            guard.phase == GamePhase::Submitting
+            guard.phase == GamePhase::Submitting && !guard.redrawing && !guard.sits_out(player_id)
         };
         let can_submit =
             { !self.is_czar(player_id).await && !self.has_submitted(player_id).await && in_phase };
         if !can_submit {
             return Err(Error::CardSubmission);
         }
-        let black_card = {
-            let guard = self.state.read().await;
-            guard.black_card.clone().unwrap_or_default()
-        };
-        if black_card.fields != indexes.len() {
-            return Err(Error::CardSubmission);
-        }
 
         // perform submission
         {
             let mut guard = self.state.write().await;
+            // A redraw could have started or finished since the checks above
+            let fields = guard.black_card.as_ref().map(|b| b.fields);
+            if guard.phase != GamePhase::Submitting
+                || guard.redrawing
+                || fields != Some(indexes.len())
+                || guard
+                    .submissions
+                    .submitted_by_player
+                    .contains_key(player_id)
+            {
+                return Err(Error::CardSubmission);
+            }
             let player = guard
                 .players
                 .get_mut(player_id)
@@ -914,31 +2503,136 @@ impl Lobby {
                 .submissions
                 .submitted_by_player
                 .insert(*player_id, indexes.clone());
+            if auto_submitted {
+                guard.submissions.auto_submitted.insert(*player_id);
+            }
         }
 
         self.submission_notify.notify_one();
         self.emit_global(ServerEvent::CardsSubmitted {
             player_id: *player_id,
+            auto_submitted,
         });
+        if !auto_submitted {
+            self.mark_active(player_id).await;
+        }
 
         Ok(())
     }
 
     /// Czar submits choice
     pub async fn submit_czar_choice(&self, player_id: &Uuid, index: usize) -> Result<()> {
+        self.submit_czar_ranking(player_id, vec![index], false)
+            .await
+    }
+
+    /// Czar ranks the best submissions (ranked mode), `complete` requires all places to be filled
+    pub async fn submit_czar_ranking(
+        &self,
+        player_id: &Uuid,
+        indexes: Vec<usize>,
+        complete: bool,
+    ) -> Result<()> {
         let is_czar = self.is_czar(player_id).await;
-        if !is_czar
-            || self.czar_submitted().await
-            || self.state.read().await.phase != GamePhase::Judging
-            || !self.in_submitted_range(index).await
         {
-            return Err(Error::CzarChoice);
+            let mut guard = self.state.write().await;
+            let places = guard.settings.places().min(guard.submissions.len());
+            if !is_czar
+                || guard.czar_pick.is_some()
+                || guard.phase != GamePhase::Judging
+                || indexes.is_empty()
+                || indexes.len() > places
+                || (complete && indexes.len() != places)
+                || !all_unique(&indexes)
+                || indexes.iter().any(|&i| i >= guard.submissions.len())
+            {
+                return Err(Error::CzarChoice);
+            }
+            guard.czar_pick = indexes.first().copied();
+            guard.czar_ranking = indexes;
+        }
+        self.czar_notify.notify_one();
+        self.mark_active(player_id).await;
+        Ok(())
+    }
+
+    /// Czar rejects the current black card and draws a replacement
+    pub async fn redraw_black_card(&self, player_id: &Uuid) -> Result<()> {
+        let is_czar = self.is_czar(player_id).await;
+        let settings = {
+            let mut guard = self.state.write().await;
+            // only allowed before anyone submitted, as the cards were chosen for the old one
+            // Rando submits instantly, so he simply submits again
+            if !is_czar
+                || guard.phase != GamePhase::Submitting
+                || guard.redrawing
+                || guard.submissions.by_index.iter().any(|id| *id != RANDO_ID)
+                || guard.black_card_redraws >= guard.settings.black_card_redraws
+            {
+                return Err(Error::BlackCardRedraw);
+            }
+            // Nobody may submit for the old card until the new one is stored
+            guard.redrawing = true;
+            guard.settings.clone()
+        };
+
+        let drawn = BlackCard::choose_random(&self.cache, &settings).await;
+
+        let (black_card, deadline, rando_index) = {
+            let mut guard = self.state.write().await;
+            guard.redrawing = false;
+            let black_card = drawn?;
+            // The phase could have ended while drawing
+            if guard.phase != GamePhase::Submitting {
+                return Err(Error::BlackCardRedraw);
+            }
+            guard.black_card = Some(black_card.clone());
+            guard.black_card_redraws += 1;
+            guard.timer.restart();
+            let rando_index = guard.submissions.remove_player(&RANDO_ID);
+            (black_card, guard.timer.deadline_ms(), rando_index)
+        };
+        if let Some(index) = rando_index {
+            self.emit_global(ServerEvent::SubmissionRemoved { index });
         }
+
+        self.timer_notify.notify_one();
+        self.emit_global(ServerEvent::RedrawBlackCard {
+            czar_id: *player_id,
+            black_card,
+        });
+        self.emit_global(ServerEvent::PhaseDeadline { deadline });
+        self.submit_rando().await;
+
+        Ok(())
+    }
+
+    /// Player votes for a submission other than their own
+    pub async fn submit_vote(&self, player_id: &Uuid, index: usize) -> Result<()> {
         {
             let mut guard = self.state.write().await;
-            guard.czar_pick = Some(index);
+            let can_vote = (guard.settings.game_mode == GameMode::Voting || guard.fallback_vote)
+                && guard.phase == GamePhase::Judging
+                && *player_id != RANDO_ID
+                && guard.players.contains_key(player_id)
+                && !guard.votes.contains_key(player_id)
+                && guard
+                    .submissions
+                    .by_index
+                    .get(index)
+                    .is_some_and(|id| id != player_id);
+            if !can_vote {
+                return Err(Error::Vote);
+            }
+            guard.votes.insert(*player_id, index);
         }
+
         self.czar_notify.notify_one();
+        self.emit_global(ServerEvent::PlayerVoted {
+            player_id: *player_id,
+        });
+        self.mark_active(player_id).await;
+
         Ok(())
     }
 
@@ -962,21 +2656,35 @@ impl Lobby {
     pub async fn reset_game(&self, player_id: &Uuid) -> Result<()> {
         if self.is_host(player_id).await && self.has_phase(GamePhase::GameOver).await {
             // reset player state
-            {
+            let seated = {
                 let mut guard = self.state.write().await;
                 guard.round = 0;
                 guard.phase = GamePhase::LobbyOpen;
+                guard.team_points.clear();
+                guard.forced_czar = None;
+                guard.winners.clear();
+                guard.standings.clear();
+                guard.team_standings.clear();
+                guard.game_over_reason = None;
+                let seated = guard.seat_pending();
                 for p in guard.players.values_mut() {
                     p.info.is_czar = false;
                     p.info.points = 0;
                     p.cards.clear();
                 }
-            }
+                seated
+            };
 
             // clear the current game task
             self.cancel_task().await;
 
             // emit reset
+            for (player_id, player_info) in seated {
+                self.emit_global(ServerEvent::PlayerJoin {
+                    player_id,
+                    player_info,
+                });
+            }
             self.emit_global(ServerEvent::LobbyReset);
 
             Ok(())
@@ -985,22 +2693,51 @@ impl Lobby {
         }
     }
 
-    pub async fn min_players(&self) -> bool {
-        let guard = self.state.read().await;
-        guard.players.len() >= 2
+    pub async fn round_skipped(&self) -> bool {
+        self.state.read().await.round_skipped
     }
 
-    pub async fn min_decks(&self) -> Result<bool> {
+    /// Checks everything a game needs before it can start
+    pub async fn check_start_requirements(&self) -> Result<()> {
         let guard = self.state.read().await;
-        let decks_enabled = guard.settings.decks.iter().any(|f| f.enabled);
-
         let settings = &guard.settings;
+
+        if guard.phase != GamePhase::LobbyOpen {
+            return Err(Error::LobbyStart(StartRequirement::LobbyOpen));
+        }
+
+        let current = guard.player_count() as u32;
+        if current < settings.min_players {
+            return Err(Error::LobbyStart(StartRequirement::Players {
+                required: settings.min_players,
+                current,
+            }));
+        }
+
         let decks = Deck::get_enabled(&self.cache, settings).await?;
+        if decks.is_empty() {
+            return Err(Error::LobbyStart(StartRequirement::Decks));
+        }
+
+        let required = decks
+            .iter()
+            .flat_map(|d| d.blacks.iter())
+            .map(|b| b.fields)
+            .max()
+            .ok_or(Error::LobbyStart(StartRequirement::BlackCards))?;
+        if required > settings.hand_size {
+            return Err(Error::LobbyStart(StartRequirement::HandSize { required }));
+        }
 
-        let has_all_kinds = decks.iter().any(|f| !f.blacks.is_empty())
-            && decks.iter().any(|f| !f.whites.is_empty());
+        let available = decks.iter().map(|d| d.whites.len()).sum();
+        if available < settings.hand_size {
+            return Err(Error::LobbyStart(StartRequirement::WhiteCards {
+                required: settings.hand_size,
+                available,
+            }));
+        }
 
-        Ok(decks_enabled && has_all_kinds)
+        Ok(())
     }
 
     pub async fn has_phase(&self, phase: GamePhase) -> bool {
@@ -1031,16 +2768,6 @@ impl Lobby {
         guard.submissions.by_index.iter().any(|id| id == player_id)
     }
 
-    pub async fn czar_submitted(&self) -> bool {
-        let guard = self.state.read().await;
-        guard.czar_pick.is_some()
-    }
-
-    pub async fn all_player_submitted(&self) -> bool {
-        let guard = self.state.read().await;
-        guard.submissions.len() == guard.players.len().saturating_sub(1)
-    }
-
     pub async fn in_submitted_range(&self, index: usize) -> bool {
         index < self.state.read().await.submissions.len()
     }
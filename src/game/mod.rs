@@ -4,13 +4,15 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::{
-    error::Error,
+    error::{Error, Result},
     game::{
-        deck::{BlackCard, DeckInfo, WhiteCard},
-        lobby::{GamePhase, LobbyData},
+        bot::BotStrategy,
+        deck::{BlackCard, DeckInfo, DeckMeta, WhiteCard},
+        lobby::{GamePhase, LobbyData, MAX_HAND_SIZE, MIN_PLAYERS},
     },
 };
 
+pub mod bot;
 pub mod deck;
 pub mod lobby;
 
@@ -20,6 +22,10 @@ pub mod lobby;
 pub enum ClientEvent {
     /// Client wants to join a lobby
     JoinLobby { credentials: Credentials },
+    /// Client wants to watch a lobby without playing
+    SpectateLobby { credentials: Credentials },
+    /// Host turns a spectator into a player (only while the lobby is open)
+    PromoteSpectator { spectator_id: Uuid },
     /// Client updates game settings (only host allowed)
     UpdateSettings { settings: Settings },
     /// Add a deck (only host allowed)
@@ -38,6 +44,32 @@ pub enum ClientEvent {
     SubmitOwnCards { indexes: Vec<usize> },
     /// Czar picks a winning card
     CzarPick { index: usize },
+    /// Czar ranks the best submissions, best first (ranked mode)
+    CzarRank { indexes: Vec<usize> },
+    /// Czar rejects the current black card and draws a new one
+    RedrawBlackCard,
+    /// A player votes for a submission in the voting mode
+    Vote { index: usize },
+    /// Client adds a bot player (only host allowed)
+    AddBot { strategy: BotStrategy },
+    /// Client removes a bot player (only host allowed)
+    RemoveBot { bot_id: Uuid },
+    /// Client freezes the timers of the running game (only host allowed)
+    Pause,
+    /// Client lets the timers continue (only host allowed)
+    Resume,
+    /// Client skips the current round, submitted cards go back to the hands (only host allowed)
+    SkipRound,
+    /// Client decides who becomes the next czar (only host allowed)
+    ForceCzar { player_id: Uuid },
+    /// A player pays points to trade in their hand for a fresh one
+    Mulligan,
+    /// Client joins a team while the lobby is open, `None` leaves it
+    JoinTeam { team: Option<usize> },
+    /// Client sends a chat message to everyone in the lobby
+    Chat { text: String },
+    /// Client (un)mutes a player or spectator in the chat (only host allowed)
+    Mute { player_id: Uuid, muted: bool },
     /// Client leaves the current lobby
     LeaveLobby,
 }
@@ -51,32 +83,92 @@ pub enum ServerEvent {
         player_id: Uuid,
         player_info: PlayerInfo,
     },
+    /// A player joined a running game and gets dealt in at the next round
+    PlayerPending {
+        player_id: Uuid,
+        player_info: PlayerInfo,
+    },
     /// A player was removed, either by the host or due to a timeout
     PlayerRemove { player_id: Uuid },
+    /// A player joined a team, teamless players get one at the start of the game
+    TeamJoin {
+        player_id: Uuid,
+        team: Option<usize>,
+    },
+    /// A spectator started watching the lobby
+    SpectatorJoin { spectator_id: Uuid, name: String },
+    /// A spectator stopped watching, left or got promoted to a player
+    SpectatorRemove { spectator_id: Uuid },
     /// The host left the lobby, assigns a new player to be one
     AssignHost { player_id: Uuid },
-    /// The Round starts, a player has been selected as the czar with their card
+    /// The Round starts, a player has been selected as the czar (none in the voting mode) with their card
     StartRound {
+        czar_id: Option<Uuid>,
+        black_card: BlackCard,
+    },
+    /// The czar redrew the black card, the submission timer starts again
+    RedrawBlackCard {
         czar_id: Uuid,
         black_card: BlackCard,
     },
-    /// A card has been submitted by a player
-    CardsSubmitted { player_id: Uuid },
+    /// A card has been submitted by a player, or by the server after they missed the deadline
+    CardsSubmitted {
+        player_id: Uuid,
+        auto_submitted: bool,
+    },
     /// The Decks has been updated by the host, via a kick
     UpdateDecks { decks: Vec<DeckInfo> },
     /// The Settings has been updated by the host
     UpdateSettings { settings: Settings },
+    /// The host enabled decks which aren't in the lobby language
+    LanguageMismatch { deckcodes: Vec<String> },
+    /// The submission at `index` was dropped, because its player left
+    SubmissionRemoved { index: usize },
     /// Reveal all submitted cards to all players
     RevealCards { selected_cards: Vec<Vec<WhiteCard>> },
     /// The round was skipped
     RoundSkip,
-    /// The round result after czar picks
+    /// The host decided that the player becomes the next czar
+    NextCzar { player_id: Uuid },
+    /// A player voted for a submission
+    PlayerVoted { player_id: Uuid },
+    /// The votes per revealed submission after voting ended
+    VoteTally { votes: Vec<u32> },
+    /// The czar didn't pick in time, everyone votes for the winner instead
+    CzarVote,
+    /// A player missed too many rounds in a row or acted again
+    PlayerAfk { player_id: Uuid, is_afk: bool },
+    /// A player or spectator wrote in the chat
+    ChatMessage(ChatMessage),
+    /// The host (un)muted a player or spectator in the chat
+    PlayerMute { player_id: Uuid, muted: bool },
+    /// The round result after czar picks, `player_id` and `winning_card_index` belong to the first place.
+    /// `automatic` if the czar didn't pick in time
     RoundResult {
         player_id: Uuid,
         winning_card_index: usize,
+        placements: Vec<Placement>,
+        automatic: bool,
+    },
+    /// A player traded in their hand, leaving them with `points`
+    Mulligan { player_id: Uuid, points: u32 },
+    /// The current phase ends at `deadline` (unix timestamp in milliseconds), `None` without a timer or while paused
+    PhaseDeadline { deadline: Option<u64> },
+    /// The host paused the game, the current phase had `remaining_secs` left
+    Paused { remaining_secs: Option<u64> },
+    /// The host resumed the game, the current phase has `remaining_secs` left
+    Resumed { remaining_secs: Option<u64> },
+    /// The end condition was reached with a tie, the tied players play extra rounds on their own
+    SuddenDeath { player_ids: Vec<Uuid> },
+    /// The game is over, several winners share the win and the standings are sorted best first.
+    /// The team standings decide the game in the team mode
+    GameOver {
+        reason: GameOverReason,
+        winners: Vec<Uuid>,
+        standings: Vec<Standing>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        team_standings: Vec<TeamStanding>,
     },
-    /// The game is over due to reaching max rounds
-    GameOver,
     /// The lobby has been reset
     LobbyReset,
 }
@@ -100,6 +192,10 @@ pub enum PrivateServerEvent {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ClientLobby {
     players: HashMap<Uuid, PlayerInfo>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pending: HashMap<Uuid, PlayerInfo>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    spectators: HashMap<Uuid, String>,
     settings: Settings,
     phase: GamePhase,
     round: u32,
@@ -111,12 +207,86 @@ pub struct ClientLobby {
     submitted_players: Vec<Uuid>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     selected_cards: Vec<usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    voted_players: Vec<Uuid>,
+    paused: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deadline: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     czar_pick: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
     winner: Option<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    placements: Vec<Placement>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    team_points: Vec<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sudden_death: Vec<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    winners: Vec<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    standings: Vec<Standing>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    team_standings: Vec<TeamStanding>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    game_over_reason: Option<GameOverReason>,
     #[serde(skip_serializing_if = "Option::is_none")]
     black_card: Option<BlackCard>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    chat: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    muted: Vec<Uuid>,
+}
+
+/// A submission awarded with points at the end of a round
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Placement {
+    pub player_id: Uuid,
+    pub card_index: usize,
+    pub points: u32,
+    pub auto_submitted: bool,
+}
+
+/// Why the game ended
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameOverReason {
+    MaxRounds,
+    MaxPoints,
+    /// The host ended the game early
+    HostEnded,
+    /// Too few players are left to go on
+    PlayerLeft,
+    /// The enabled decks ran out of cards
+    NotEnoughCards,
+    InternalError,
+}
+
+/// The final result of a player
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Standing {
+    pub player_id: Uuid,
+    pub name: String,
+    pub points: u32,
+    pub rounds_won: u32,
+}
+
+/// The final result of a team, `rounds_won` by all its members
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TeamStanding {
+    pub team: usize,
+    pub name: String,
+    pub points: u32,
+    pub rounds_won: u32,
+    pub player_ids: Vec<Uuid>,
+}
+
+/// A message in the lobby chat, `timestamp` in unix ms
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChatMessage {
+    pub player_id: Uuid,
+    pub name: String,
+    pub text: String,
+    pub timestamp: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -125,6 +295,10 @@ pub struct PlayerInfo {
     is_host: bool,
     is_czar: bool,
     points: u32,
+    is_rando: bool,
+    is_bot: bool,
+    team: Option<usize>,
+    is_afk: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -134,6 +308,54 @@ pub struct Player {
     cards: Vec<WhiteCard>,
 }
 
+/// Someone watching the lobby, only receiving the global events
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Spectator {
+    secret: Uuid,
+    name: String,
+}
+
+/// Rando Cardrissian always uses the nil id, no client can log in as him
+pub const RANDO_ID: Uuid = Uuid::nil();
+
+impl Player {
+    /// A server side player, driven by a `Bot`
+    pub fn bot(name: String) -> Self {
+        Self {
+            secret: Uuid::new_v4(),
+            info: PlayerInfo {
+                name,
+                is_host: false,
+                is_czar: false,
+                points: 0,
+                is_rando: false,
+                is_bot: true,
+                team: None,
+                is_afk: false,
+            },
+            cards: Vec::new(),
+        }
+    }
+
+    /// The phantom player submitting random cards each round
+    pub fn rando() -> Self {
+        Self {
+            secret: Uuid::new_v4(),
+            info: PlayerInfo {
+                name: String::from("Rando Cardrissian"),
+                is_host: false,
+                is_czar: false,
+                points: 0,
+                is_rando: true,
+                is_bot: false,
+                team: None,
+                is_afk: false,
+            },
+            cards: Vec::new(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Credentials {
     pub name: String,
@@ -149,6 +371,26 @@ pub struct Settings {
     pub max_judging_time_secs: Option<u64>,
     pub wait_time_secs: Option<u64>,
     pub max_players: u32,
+    pub min_players: u32,
+    pub hand_size: usize,
+    pub black_card_redraws: u32,
+    pub language: Option<String>,
+    pub only_matching_languages: bool,
+    pub allow_nsfw: bool,
+    pub rando_cardrissian: bool,
+    pub game_mode: GameMode,
+    pub vote_tie: VoteTie,
+    pub ranked_points: Option<Vec<u32>>,
+    pub join_in_progress: bool,
+    pub catch_up: CatchUp,
+    pub teams: Option<Vec<String>>,
+    pub czar_rotation: CzarRotation,
+    pub mulligan: Option<Mulligan>,
+    pub tie_break: TieBreak,
+    pub auto_submit: bool,
+    pub czar_timeout: CzarTimeout,
+    pub afk_rounds: Option<u32>,
+    pub kick_afk: bool,
     pub decks: Vec<DeckInfo>,
 }
 
@@ -161,18 +403,42 @@ impl Default for Settings {
             max_judging_time_secs: Some(30),
             wait_time_secs: Some(5),
             max_players: 20,
+            min_players: MIN_PLAYERS as u32,
+            hand_size: 10,
+            black_card_redraws: 1,
+            language: None,
+            only_matching_languages: false,
+            allow_nsfw: true,
+            rando_cardrissian: false,
+            game_mode: GameMode::default(),
+            vote_tie: VoteTie::default(),
+            ranked_points: None,
+            join_in_progress: true,
+            catch_up: CatchUp::default(),
+            teams: None,
+            czar_rotation: CzarRotation::default(),
+            mulligan: None,
+            tie_break: TieBreak::default(),
+            auto_submit: false,
+            czar_timeout: CzarTimeout::default(),
+            afk_rounds: Some(2),
+            kick_afk: false,
             decks: Vec::new(),
         }
     }
 }
 
 impl Settings {
-    pub fn end_condition_reached(&self, lobby_data: &LobbyData) -> bool {
+    /// The end condition which was reached, if any
+    pub fn end_reason(&self, lobby_data: &LobbyData) -> Option<GameOverReason> {
         let max_rounds_reached = match self.max_rounds {
             Some(max_rounds) => lobby_data.round >= max_rounds,
             None => false,
         };
         let max_points_reached = match self.max_points {
+            Some(max_points) if self.team_count() > 0 => {
+                lobby_data.team_points.iter().any(|&p| p >= max_points)
+            }
             Some(max_points) => lobby_data
                 .players
                 .iter()
@@ -180,7 +446,194 @@ impl Settings {
             None => false,
         };
 
-        max_rounds_reached || max_points_reached
+        if max_points_reached {
+            Some(GameOverReason::MaxPoints)
+        } else if max_rounds_reached {
+            Some(GameOverReason::MaxRounds)
+        } else {
+            None
+        }
+    }
+
+    /// Checks the ranges which don't depend on the decks
+    pub fn validate(&self) -> Result<()> {
+        if self.min_players < MIN_PLAYERS as u32 || self.min_players > self.max_players {
+            return Err(Error::Settings(format!(
+                "The minimum player count has to be between {MIN_PLAYERS} and the maximum player count"
+            )));
+        }
+        if !(1..=MAX_HAND_SIZE).contains(&self.hand_size) {
+            return Err(Error::Settings(format!(
+                "The hand size has to be between 1 and {MAX_HAND_SIZE}"
+            )));
+        }
+        if let Some(points) = &self.ranked_points {
+            if points.first().is_none_or(|&first| first == 0)
+                || points.windows(2).any(|w| w[0] < w[1])
+            {
+                return Err(Error::Settings(
+                    "Ranked points need a scoring first place and may not increase for lower places"
+                        .to_string(),
+                ));
+            }
+        }
+        if self.afk_rounds == Some(0) {
+            return Err(Error::Settings(
+                "Players can't be marked as AFK before missing a round".to_string(),
+            ));
+        }
+        if self.teams.as_ref().is_some_and(|t| t.len() < 2) {
+            return Err(Error::Team);
+        }
+        Ok(())
+    }
+
+    /// Number of teams, zero without the team mode
+    pub fn team_count(&self) -> usize {
+        self.teams.as_ref().map(|t| t.len()).unwrap_or_default()
+    }
+
+    /// Points for the given place, a single point for the winner without the ranked mode
+    pub fn points_for_place(&self, place: usize) -> u32 {
+        match &self.ranked_points {
+            Some(points) => points.get(place).copied().unwrap_or_default(),
+            None if place == 0 => 1,
+            None => 0,
+        }
+    }
+
+    /// How many submissions the czar has to rank
+    pub fn places(&self) -> usize {
+        self.ranked_points
+            .as_ref()
+            .map(|p| p.len().max(1))
+            .unwrap_or(1)
+    }
+
+    /// Whether a deck is in the lobby language, always true if no language is set
+    pub fn matches_language(&self, meta: &DeckMeta) -> bool {
+        language_matches(self.language.as_deref(), meta)
+    }
+
+    /// Filters or sorts the decks by the lobby language, matching ones first
+    pub fn arrange_decks(&mut self) {
+        let language = self.language.as_deref();
+        if self.only_matching_languages && language.is_some() {
+            self.decks.retain(|d| language_matches(language, &d.meta));
+        }
+        self.decks.sort_by_cached_key(|d| {
+            (
+                !language_matches(language, &d.meta),
+                d.meta.name.to_lowercase(),
+            )
+        });
+    }
+}
+
+fn language_matches(language: Option<&str>, meta: &DeckMeta) -> bool {
+    language.is_none_or(|l| meta.language.eq_ignore_ascii_case(l.trim()))
+}
+
+/// Who decides the winner of a round
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameMode {
+    /// A rotating czar picks the winner
+    #[default]
+    Czar,
+    /// Everyone submits and votes for a submission other than their own
+    Voting,
+}
+
+/// Trading in the whole hand for a fresh one ("Rebooting the Universe")
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Mulligan {
+    /// Points a player pays for a new hand
+    pub cost: u32,
+    /// How often a player can trade in per round
+    pub per_round: u32,
+    /// The phases in which trading in is allowed
+    pub phases: Vec<GamePhase>,
+}
+
+impl Default for Mulligan {
+    fn default() -> Self {
+        Self {
+            cost: 1,
+            per_round: 1,
+            phases: vec![GamePhase::Submitting],
+        }
+    }
+}
+
+/// How a tie for the most points is resolved when the game ends
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TieBreak {
+    /// Every tied player wins
+    #[default]
+    Shared,
+    /// The tied players play extra rounds on their own
+    SuddenDeath,
+    /// The tied player who won the most rounds picked by a czar wins
+    RoundsWon,
+}
+
+/// Who becomes the next czar
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CzarRotation {
+    /// Everyone in join order
+    #[default]
+    Sequential,
+    /// Anyone at random
+    Random,
+    /// The winner of the last round, otherwise the next one in join order
+    Winner,
+    /// Whoever was czar the longest time ago, players who never were go first
+    LeastRecent,
+}
+
+/// What happens when the czar doesn't pick in time
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CzarTimeout {
+    /// Nobody wins the round
+    #[default]
+    Skip,
+    /// A random submission wins
+    Random,
+    /// Everyone quickly votes for a submission other than their own
+    Vote,
+}
+
+/// How a tie for the most votes is resolved
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum VoteTie {
+    /// Every tied player gets a point
+    #[default]
+    Shared,
+    /// One of the tied players is chosen at random
+    Random,
+    /// Nobody gets a point
+    Skip,
+}
+
+/// The points a player joining a running game starts with
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CatchUp {
+    #[default]
+    Zero,
+    /// The points of the worst player
+    Lowest,
+    /// The average points, rounded down
+    Average,
+}
+
+impl CatchUp {
+    pub fn points(self, scores: &[u32]) -> u32 {
+        match self {
+            CatchUp::Zero => 0,
+            CatchUp::Lowest => scores.iter().copied().min().unwrap_or_default(),
+            CatchUp::Average if scores.is_empty() => 0,
+            CatchUp::Average => scores.iter().sum::<u32>() / scores.len() as u32,
+        }
     }
 }
 
@@ -3,7 +3,12 @@ pub mod game;
 pub mod server;
 pub mod utils;
 
-use std::{fs, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 use axum::{
     error_handling::HandleErrorLayer,
@@ -20,10 +25,13 @@ use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
-use crate::server::{create_lobby, stats, ws::ws_handler, ServerState};
+use crate::{
+    game::deck::{Deck, ImportOutcome},
+    server::{create_lobby, stats, ws::ws_handler, ServerState},
+};
 
 /// General timeout interval is 30 Minutes
 pub const TIMEOUT_INTERVAL: Duration = Duration::from_secs(30 * 60);
@@ -35,7 +43,16 @@ pub const GRACE_PERIOD: Duration = Duration::from_secs(60);
 #[command(name = env!("CARGO_PKG_NAME"))]
 struct Args {
     /// Host in the format ip:port
-    host: String,
+    #[arg(required_unless_present = "import")]
+    host: Option<String>,
+
+    /// Hide nsfw decks on the whole server
+    #[arg(long)]
+    no_nsfw: bool,
+
+    /// Import all decks of a zip archive into the cache folder and exit
+    #[arg(long, value_name = "ZIP")]
+    import: Option<PathBuf>,
 
     /// To enable cross origin reguests from the frontend
     #[arg(short, default_value = "https://clash.nwrenger.dev")]
@@ -74,6 +91,12 @@ async fn main() {
         fs::create_dir_all(&args.cache).unwrap();
     }
 
+    if let Some(archive) = &args.import {
+        import(&args.cache, archive).await;
+        return;
+    }
+    let host = args.host.expect("host is required without --import");
+
     if !PathBuf::from(&args.cert).exists() {
         error!("The SSL certificate path {:?} does not exist!", args.cert);
         std::process::exit(1);
@@ -85,7 +108,7 @@ async fn main() {
     }
 
     // Init some ws sockets and lobbies state
-    let state = Arc::new(ServerState::new(args.cache));
+    let state = Arc::new(ServerState::new(args.cache, !args.no_nsfw));
 
     // Spawn the janitor
     {
@@ -127,16 +150,12 @@ async fn main() {
                 .into_inner(),
         );
 
-    let tcp = TcpListener::bind(&args.host)
-        .await
-        .unwrap()
-        .into_std()
-        .unwrap();
+    let tcp = TcpListener::bind(&host).await.unwrap().into_std().unwrap();
     let tls = RustlsConfig::from_pem_file(&args.cert, &args.key)
         .await
         .unwrap();
 
-    info!("Server started on \"{}\"", args.host);
+    info!("Server started on \"{}\"", host);
 
     axum_server::from_tcp_rustls(tcp, tls)
         .unwrap()
@@ -145,6 +164,46 @@ async fn main() {
         .unwrap();
 }
 
+/// Import a zip archive of decks and log a report for every file
+async fn import(cache: &Path, archive: &Path) {
+    let report = match Deck::import_archive(cache, archive).await {
+        Ok(report) => report,
+        Err(e) => {
+            error!("Failed to open the archive {:?}: {:?}", archive, e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut imported = 0;
+    for entry in &report {
+        match &entry.outcome {
+            ImportOutcome::Imported {
+                deckcode,
+                name,
+                replaced,
+            } => {
+                imported += 1;
+                if *replaced {
+                    warn!(
+                        "Imported {:?} as {} ({}), replacing the cached deck",
+                        entry.file, deckcode, name
+                    );
+                } else {
+                    info!("Imported {:?} as {} ({})", entry.file, deckcode, name);
+                }
+            }
+            ImportOutcome::Duplicate { deckcode, file } => {
+                warn!(
+                    "Skipped {:?}: {} was already imported from {:?}",
+                    entry.file, deckcode, file
+                )
+            }
+            ImportOutcome::Failed(e) => error!("Skipped {:?}: {:?}", entry.file, e),
+        }
+    }
+    info!("Imported {} of {} decks", imported, report.len());
+}
+
 /// Initialize tracing
 fn logging() {
     tracing_subscriber::registry()
@@ -10,7 +10,7 @@ use uuid::Uuid;
 
 use crate::error::{Error, Result};
 use crate::game::lobby::Lobby;
-use crate::game::Credentials;
+use crate::game::{Credentials, RANDO_ID};
 use crate::TIMEOUT_INTERVAL;
 
 pub mod ws;
@@ -19,14 +19,16 @@ pub struct ServerState {
     pub lobbies: DashMap<Uuid, Arc<Lobby>>,
     pub player_count: AtomicU64,
     pub cache: PathBuf,
+    pub allow_nsfw: bool,
 }
 
 impl ServerState {
-    pub fn new(cache: PathBuf) -> Self {
+    pub fn new(cache: PathBuf, allow_nsfw: bool) -> Self {
         Self {
             lobbies: DashMap::new(),
             player_count: AtomicU64::new(0),
             cache,
+            allow_nsfw,
         }
     }
 
@@ -79,7 +81,7 @@ impl ServerState {
 
 impl Default for ServerState {
     fn default() -> Self {
-        Self::new(PathBuf::new())
+        Self::new(PathBuf::new(), true)
     }
 }
 
@@ -102,8 +104,13 @@ pub async fn create_lobby(
     State(state): State<Arc<ServerState>>,
     Json(host): Json<Credentials>,
 ) -> Result<Json<LobbyId>> {
+    // Rando's id is reserved
+    if host.id == RANDO_ID {
+        return Err(Error::LobbyLogin);
+    }
+
     let lobby_id = Uuid::new_v4();
-    let lobby = Lobby::new(state.cache.clone(), host).await?;
+    let lobby = Lobby::new(state.cache.clone(), state.allow_nsfw, host).await?;
     state.lobbies.insert(lobby_id, lobby);
 
     Ok(Json(LobbyId { id: lobby_id }))
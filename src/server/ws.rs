@@ -55,8 +55,9 @@ async fn handle_socket(socket: WebSocket, lobby: Arc<Lobby>, state: Arc<ServerSt
             return;
         }
     };
-    let credentials = match serde_json::from_str::<ClientEvent>(&txt) {
-        Ok(ClientEvent::JoinLobby { credentials }) => credentials,
+    let (credentials, spectating) = match serde_json::from_str::<ClientEvent>(&txt) {
+        Ok(ClientEvent::JoinLobby { credentials }) => (credentials, false),
+        Ok(ClientEvent::SpectateLobby { credentials }) => (credentials, true),
         _ => {
             send_event(&mut sender, &PrivateServerEvent::Error(Error::LobbyLogin)).await;
             return;
@@ -66,7 +67,12 @@ async fn handle_socket(socket: WebSocket, lobby: Arc<Lobby>, state: Arc<ServerSt
     // Open global and private receivers and join the lobby
     let (mut global, mut private) = {
         // When join errors it's maken sure that no players got added beforehand
-        if let Err(msg) = lobby.join(&credentials).await {
+        let joined = if spectating {
+            lobby.spectate(&credentials).await
+        } else {
+            lobby.join(&credentials).await
+        };
+        if let Err(msg) = joined {
             send_event(&mut sender, &PrivateServerEvent::Error(msg)).await;
             return;
         }
@@ -107,7 +113,12 @@ async fn handle_socket(socket: WebSocket, lobby: Arc<Lobby>, state: Arc<ServerSt
             if let Ok(event) = serde_json::from_str::<ClientEvent>(&txt) {
                 if let Err(error) = {
                     match event {
-                        ClientEvent::JoinLobby { .. } => Ok(()),
+                        ClientEvent::JoinLobby { .. } | ClientEvent::SpectateLobby { .. } => Ok(()),
+                        ClientEvent::PromoteSpectator { spectator_id } => {
+                            lobby
+                                .promote_spectator(&credentials.id, &spectator_id)
+                                .await
+                        }
                         ClientEvent::UpdateSettings { settings } => {
                             lobby.update_settings(&credentials.id, settings).await
                         }
@@ -125,6 +136,37 @@ async fn handle_socket(socket: WebSocket, lobby: Arc<Lobby>, state: Arc<ServerSt
                         ClientEvent::CzarPick { index } => {
                             lobby.submit_czar_choice(&credentials.id, index).await
                         }
+                        ClientEvent::CzarRank { indexes } => {
+                            lobby
+                                .submit_czar_ranking(&credentials.id, indexes, true)
+                                .await
+                        }
+                        ClientEvent::Vote { index } => {
+                            lobby.submit_vote(&credentials.id, index).await
+                        }
+                        ClientEvent::RedrawBlackCard => {
+                            lobby.redraw_black_card(&credentials.id).await
+                        }
+                        ClientEvent::AddBot { strategy } => {
+                            lobby.add_bot(&credentials.id, strategy).await
+                        }
+                        ClientEvent::RemoveBot { bot_id } => {
+                            lobby.remove_bot(&credentials.id, &bot_id).await
+                        }
+                        ClientEvent::Pause => lobby.pause(&credentials.id).await,
+                        ClientEvent::Resume => lobby.resume(&credentials.id).await,
+                        ClientEvent::SkipRound => lobby.skip_round(&credentials.id).await,
+                        ClientEvent::ForceCzar { player_id } => {
+                            lobby.force_czar(&credentials.id, &player_id).await
+                        }
+                        ClientEvent::Mulligan => lobby.mulligan(&credentials.id).await,
+                        ClientEvent::JoinTeam { team } => {
+                            lobby.join_team(&credentials.id, team).await
+                        }
+                        ClientEvent::Chat { text } => lobby.chat(&credentials.id, text).await,
+                        ClientEvent::Mute { player_id, muted } => {
+                            lobby.mute(&credentials.id, &player_id, muted).await
+                        }
                         ClientEvent::LeaveLobby => lobby.leave(&credentials.id).await,
                     }
                 } {
@@ -1,6 +1,17 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 pub fn all_unique(nums: &[usize]) -> bool {
     let mut seen = HashSet::new();
     nums.iter().all(|&x| seen.insert(x))
 }
+
+/// Milliseconds since the unix epoch, as understood by the clients
+pub fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
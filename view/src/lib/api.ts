@@ -5,16 +5,34 @@ namespace api {
 		| { kind: 'LobbyLogin' }
 		| { kind: 'LobbyClosed' }
 		| { kind: 'LobbyFull' }
-		| { kind: 'LobbyStart' }
+		| { kind: 'LobbyStart'; value: StartRequirement }
 		| { kind: 'LobbyNotFound' }
 		| { kind: 'CardSubmission' }
 		| { kind: 'CzarChoice' }
+		| { kind: 'Vote' }
+		| { kind: 'BlackCardRedraw' }
+		| { kind: 'Mulligan' }
+		| { kind: 'Pause' }
+		| { kind: 'SkipRound' }
+		| { kind: 'Chat' }
 		| { kind: 'Unauthorized' }
+		| { kind: 'Nsfw' }
+		| { kind: 'Team' }
+		| { kind: 'Settings'; value: string }
 		| { kind: 'Deck'; value: string }
 		| { kind: 'Reqwest'; value: string }
 		| { kind: 'FileSystem'; value: string }
 		| { kind: 'Json'; value: string };
 
+	export type StartRequirement =
+		| { kind: 'LobbyOpen' }
+		| { kind: 'Players'; value: { required: number; current: number } }
+		| { kind: 'Teams' }
+		| { kind: 'Decks' }
+		| { kind: 'BlackCards' }
+		| { kind: 'WhiteCards'; value: { required: number; available: number } }
+		| { kind: 'HandSize'; value: { required: number } };
+
 	export type Uuid = `${string}-${string}-${string}-${string}-${string}`;
 
 	export interface Credentials {
@@ -79,9 +97,47 @@ namespace api {
 		max_judging_time_secs: number | null;
 		wait_time_secs: number | null;
 		max_players: number;
+		min_players: number;
+		hand_size: number;
+		black_card_redraws: number;
+		language: string | null;
+		only_matching_languages: boolean;
+		allow_nsfw: boolean;
+		rando_cardrissian: boolean;
+		game_mode: GameMode;
+		vote_tie: VoteTie;
+		ranked_points: number[] | null;
+		join_in_progress: boolean;
+		catch_up: CatchUp;
+		teams: string[] | null;
+		czar_rotation: CzarRotation;
+		mulligan: Mulligan | null;
+		tie_break: TieBreak;
+		auto_submit: boolean;
+		czar_timeout: CzarTimeout;
+		afk_rounds: number | null;
+		kick_afk: boolean;
 		decks: DeckInfo[];
 	}
 
+	export type GameMode = 'Czar' | 'Voting';
+
+	export interface Mulligan {
+		cost: number;
+		per_round: number;
+		phases: GamePhase[];
+	}
+
+	export type CzarTimeout = 'Skip' | 'Random' | 'Vote';
+
+	export type TieBreak = 'Shared' | 'SuddenDeath' | 'RoundsWon';
+
+	export type CzarRotation = 'Sequential' | 'Random' | 'Winner' | 'LeastRecent';
+
+	export type VoteTie = 'Shared' | 'Random' | 'Skip';
+
+	export type CatchUp = 'Zero' | 'Lowest' | 'Average';
+
 	export type Scaling = { type: 'Player'; seconds: number } | { type: 'Constant'; seconds: number };
 
 	export interface DeckInfo {
@@ -97,6 +153,18 @@ namespace api {
 		blacks_count: number;
 		whites_count: number;
 		fetched_at: number;
+		author: string | null;
+		description: string | null;
+		created_at: number | null;
+		updated_at: number | null;
+		tags: string[];
+	}
+
+	export interface Placement {
+		player_id: Uuid;
+		card_index: number;
+		points: number;
+		auto_submitted: boolean;
 	}
 
 	export interface PlayerInfo {
@@ -104,10 +172,48 @@ namespace api {
 		is_host: boolean;
 		is_czar: boolean;
 		points: number;
+		is_rando: boolean;
+		is_bot: boolean;
+		team?: number;
+		is_afk: boolean;
+	}
+
+	export type GameOverReason =
+		| 'MaxRounds'
+		| 'MaxPoints'
+		| 'HostEnded'
+		| 'PlayerLeft'
+		| 'NotEnoughCards'
+		| 'InternalError';
+
+	export interface Standing {
+		player_id: Uuid;
+		name: string;
+		points: number;
+		rounds_won: number;
+	}
+
+	export interface TeamStanding {
+		team: number;
+		name: string;
+		points: number;
+		rounds_won: number;
+		player_ids: Uuid[];
 	}
 
+	export interface ChatMessage {
+		player_id: Uuid;
+		name: string;
+		text: string;
+		timestamp: number;
+	}
+
+	export type BotStrategy = 'Random' | 'LongestText';
+
 	export interface ClientLobby {
 		players: Record<Uuid, PlayerInfo>;
+		pending?: Record<Uuid, PlayerInfo>;
+		spectators?: Record<Uuid, string>;
 		settings: Settings;
 		phase: GamePhase;
 		round: number;
@@ -115,15 +221,29 @@ namespace api {
 		revealed_cards?: api.WhiteCard[][];
 		submitted_players?: Uuid[];
 		selected_cards?: number[];
+		voted_players?: Uuid[];
+		paused: boolean;
+		deadline?: number;
 		czar_pick?: number;
 		winner?: Uuid;
+		placements?: Placement[];
+		team_points?: number[];
+		sudden_death?: Uuid[];
+		winners?: Uuid[];
+		standings?: Standing[];
+		team_standings?: TeamStanding[];
+		game_over_reason?: GameOverReason;
 		black_card?: BlackCard;
+		chat?: ChatMessage[];
+		muted?: Uuid[];
 	}
 
 	export type GamePhase = 'LobbyOpen' | 'Submitting' | 'Judging' | 'RoundFinished' | 'GameOver';
 
 	export type ClientEvent =
 		| { type: 'JoinLobby'; data: { credentials: Credentials } }
+		| { type: 'SpectateLobby'; data: { credentials: Credentials } }
+		| { type: 'PromoteSpectator'; data: { spectator_id: Uuid } }
 		| { type: 'UpdateSettings'; data: { settings: Settings } }
 		| { type: 'AddDeck'; data: { deckcode: String } }
 		| { type: 'FetchDecks' }
@@ -133,20 +253,68 @@ namespace api {
 		| { type: 'RestartRound' }
 		| { type: 'SubmitOwnCards'; data: { indexes: number[] } }
 		| { type: 'CzarPick'; data: { index: number } }
+		| { type: 'CzarRank'; data: { indexes: number[] } }
+		| { type: 'Vote'; data: { index: number } }
+		| { type: 'RedrawBlackCard' }
+		| { type: 'AddBot'; data: { strategy: BotStrategy } }
+		| { type: 'RemoveBot'; data: { bot_id: Uuid } }
+		| { type: 'Pause' }
+		| { type: 'Resume' }
+		| { type: 'SkipRound' }
+		| { type: 'ForceCzar'; data: { player_id: Uuid } }
+		| { type: 'Mulligan' }
+		| { type: 'JoinTeam'; data: { team: number | null } }
+		| { type: 'Chat'; data: { text: string } }
+		| { type: 'Mute'; data: { player_id: Uuid; muted: boolean } }
 		| { type: 'LeaveLobby' };
 
 	export type ServerEvent =
 		| { type: 'PlayerJoin'; data: { player_id: Uuid; player_info: PlayerInfo } }
+		| { type: 'PlayerPending'; data: { player_id: Uuid; player_info: PlayerInfo } }
 		| { type: 'PlayerRemove'; data: { player_id: Uuid } }
+		| { type: 'TeamJoin'; data: { player_id: Uuid; team: number | null } }
+		| { type: 'SpectatorJoin'; data: { spectator_id: Uuid; name: string } }
+		| { type: 'SpectatorRemove'; data: { spectator_id: Uuid } }
 		| { type: 'AssignHost'; data: { player_id: Uuid } }
-		| { type: 'StartRound'; data: { czar_id: Uuid; black_card: BlackCard } }
-		| { type: 'CardsSubmitted'; data: { player_id: Uuid } }
+		| { type: 'StartRound'; data: { czar_id: Uuid | null; black_card: BlackCard } }
+		| { type: 'RedrawBlackCard'; data: { czar_id: Uuid; black_card: BlackCard } }
+		| { type: 'CardsSubmitted'; data: { player_id: Uuid; auto_submitted: boolean } }
 		| { type: 'UpdateDecks'; data: { decks: DeckInfo[] } }
 		| { type: 'UpdateSettings'; data: { settings: Settings } }
+		| { type: 'LanguageMismatch'; data: { deckcodes: string[] } }
+		| { type: 'SubmissionRemoved'; data: { index: number } }
 		| { type: 'RevealCards'; data: { selected_cards: WhiteCard[][] } }
 		| { type: 'RoundSkip' }
-		| { type: 'RoundResult'; data: { player_id: Uuid; winning_card_index: number } }
-		| { type: 'GameOver' }
+		| { type: 'NextCzar'; data: { player_id: Uuid } }
+		| { type: 'PlayerVoted'; data: { player_id: Uuid } }
+		| { type: 'CzarVote' }
+		| { type: 'PlayerAfk'; data: { player_id: Uuid; is_afk: boolean } }
+		| { type: 'ChatMessage'; data: ChatMessage }
+		| { type: 'PlayerMute'; data: { player_id: Uuid; muted: boolean } }
+		| { type: 'VoteTally'; data: { votes: number[] } }
+		| {
+				type: 'RoundResult';
+				data: {
+					player_id: Uuid;
+					winning_card_index: number;
+					placements: Placement[];
+					automatic: boolean;
+				};
+		  }
+		| { type: 'Mulligan'; data: { player_id: Uuid; points: number } }
+		| { type: 'PhaseDeadline'; data: { deadline: number | null } }
+		| { type: 'Paused'; data: { remaining_secs: number | null } }
+		| { type: 'Resumed'; data: { remaining_secs: number | null } }
+		| { type: 'SuddenDeath'; data: { player_ids: Uuid[] } }
+		| {
+				type: 'GameOver';
+				data: {
+					reason: GameOverReason;
+					winners: Uuid[];
+					standings: Standing[];
+					team_standings?: TeamStanding[];
+				};
+		  }
 		| { type: 'LobbyReset' };
 
 	export type PrivateServerEvent =
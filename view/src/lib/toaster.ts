@@ -31,7 +31,16 @@ function is_error(result: any): result is api.Error {
 		'LobbyNotFound',
 		'CardSubmission',
 		'CzarChoice',
+		'Vote',
+		'Mulligan',
+		'Pause',
+		'SkipRound',
+		'Chat',
+		'BlackCardRedraw',
 		'Unauthorized',
+		'Nsfw',
+		'Team',
+		'Settings',
 		'Deck',
 		'Reqwest',
 		'FileSystem',
@@ -77,10 +86,7 @@ function error_msg(error: api.Error): Options {
 				description: `The lobby you're trying to join is already full.`
 			};
 		case 'LobbyStart':
-			return {
-				title: `Lobby couldn't be started`,
-				description: `We couldn't start the lobby because your configuration, or having only one player in the lobby, would leave the game in an unplayable state.`
-			};
+			return { title: `Lobby couldn't be started`, description: start_requirement_msg(error.value) };
 		case 'LobbyNotFound':
 			return {
 				title: 'Lobby Not Found',
@@ -96,11 +102,53 @@ function error_msg(error: api.Error): Options {
 				title: 'Czar Choice',
 				description: `Your choice as a Czar couln't be submitted. This might be happening due to a Game Phase missmatch.`
 			};
+		case 'Vote':
+			return {
+				title: 'Vote',
+				description: `Your vote couldn't be submitted. You can't vote for your own cards or vote twice.`
+			};
+		case 'BlackCardRedraw':
+			return {
+				title: 'Black Card Redraw',
+				description: `The black card couldn't be redrawn. Either someone already submitted cards or there are no redraws left this round.`
+			};
 		case 'Unauthorized':
 			return {
 				title: 'Authorization Error',
 				description: `You're not authorized to due that action.`
 			};
+		case 'Mulligan':
+			return {
+				title: 'Mulligan',
+				description: `Your hand couldn't be traded in. It's disabled, not allowed right now, too expensive or already used up this round.`
+			};
+		case 'Pause':
+			return {
+				title: 'Pause',
+				description: `The game couldn't be paused or resumed. It isn't running or already in that state.`
+			};
+		case 'SkipRound':
+			return {
+				title: 'Skip Round',
+				description: `The round couldn't be skipped. No round is being played or it's already skipped.`
+			};
+		case 'Chat':
+			return {
+				title: 'Chat',
+				description: `Your message couldn't be sent. It's empty or too long, you're writing too fast or you've been muted.`
+			};
+		case 'Team':
+			return {
+				title: 'Team',
+				description: `The team doesn't exist, the lobby isn't open or less than two teams are configured.`
+			};
+		case 'Settings':
+			return { title: 'Invalid Settings', description: error.value };
+		case 'Nsfw':
+			return {
+				title: 'NSFW Not Allowed',
+				description: `NSFW decks aren't allowed in this lobby or on this server.`
+			};
 		case 'Deck':
 			return { title: 'Deck Error', description: error.value };
 		case 'Reqwest':
@@ -116,3 +164,23 @@ function error_msg(error: api.Error): Options {
 			};
 	}
 }
+
+/** Explains which requirement stopped the game from starting */
+function start_requirement_msg(requirement: api.StartRequirement): string {
+	switch (requirement.kind) {
+		case 'LobbyOpen':
+			return `A game is already running in this lobby.`;
+		case 'Players':
+			return `At least ${requirement.value.required} players are needed, but only ${requirement.value.current} joined.`;
+		case 'Teams':
+			return `At least two teams need players in the team mode.`;
+		case 'Decks':
+			return `No deck is enabled. Enable at least one deck in the settings.`;
+		case 'BlackCards':
+			return `The enabled decks don't contain any black cards.`;
+		case 'WhiteCards':
+			return `A hand holds ${requirement.value.required} cards, but the enabled decks only have ${requirement.value.available} white cards.`;
+		case 'HandSize':
+			return `A black card needs ${requirement.value.required} cards, which is more than the hand size.`;
+	}
+}